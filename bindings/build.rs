@@ -16,5 +16,8 @@
 // }
 
 fn main() {
-    let _ = embed_manifest::embed_manifest_file("bindings/app.manifest");
+    // Windows の exe だけ  (他の OS では cargo test だけ)
+    if std::env::var_os("CARGO_CFG_WINDOWS").is_some() {
+        let _ = embed_manifest::embed_manifest_file("bindings/app.manifest");
+    }
 }
@@ -0,0 +1,2317 @@
+use std::{
+    *,
+    thread::*,
+    collections::HashMap,
+    sync::Mutex,
+    sync::mpsc::*,
+};
+use windows::{
+    core::*,
+    imp::WaitForSingleObject,
+    Win32,
+    Win32::{
+        Foundation::*,
+        Graphics::Gdi::*,
+        System::LibraryLoader::GetModuleHandleW,
+        System::Threading::*,
+        UI::Input::KeyboardAndMouse::*,
+        UI::WindowsAndMessaging::*,
+        UI::HiDpi::*,
+        Devices::Display::*,
+        System::ProcessStatus::*,
+    },
+};
+use once_cell::sync::Lazy;
+use chrono::*;
+use regex::*;
+use anyhow::*;
+use winput::*;
+use crate::filter::format_strftime;
+use crate::{filter, condition, custom_format, holiday, astro, koyomi, duration, timescale, fiscal, timezone, business, metric, gpu, network, probe, tick, metric_win};
+
+fn convert_utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+pub fn convert_utf16_null(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+fn is_lock_screen() -> bool {
+    unsafe {
+        let mut hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            // ?
+            return true;
+        }
+        loop {
+            let mut classname:Vec<u8> = vec![0; 100];
+            GetClassNameA(hwnd, &mut classname);
+            let a = String::from_utf8(classname).unwrap();
+            let b = a.trim_end_matches(char::from(0));
+            if b == "Windows.UI.Core.CoreWindow" {
+                let mut pid: u32 = 0;
+                GetWindowThreadProcessId(hwnd, Some(&mut pid));
+                let hnd = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ , false, pid).unwrap();
+                let mut modulename:Vec<u8> = vec![0; 100];
+                GetModuleBaseNameA(hnd, None, &mut modulename);
+                let a = String::from_utf8(modulename).unwrap();
+                let b = a.trim_end_matches(char::from(0));            
+                CloseHandle(hnd);
+                if b.to_lowercase() == "lockapp.exe" {
+                    return true;
+                }
+            } else if b == "LockScreenControllerProxyWindow" {
+                // ?
+                return true;
+            }            
+            hwnd = GetWindow(hwnd, GW_HWNDNEXT);
+            if hwnd.0 == 0 {
+                return false;
+            }
+        }        
+    }
+}
+
+fn error_messagebox(msg1: &str, msg2: &str) {
+    unsafe {
+        let msg = "[Error] ".to_string() + msg1 + "\n\n" + msg2;
+        let text = convert_utf16_null(&msg);
+        let caption  = convert_utf16_null("tcc-win11");
+        MessageBoxW(
+            None,
+            PCWSTR(text.as_ptr()),
+            PCWSTR(caption.as_ptr()),
+            MB_OK
+        );
+    }
+}
+
+// tcc-win11.log  (same folder as config.txt)
+fn write_log(msg: &str) {
+    let mut log_path = env::current_exe().unwrap();
+    log_path.pop();
+    log_path.push("tcc-win11.log");
+    if let std::result::Result::Ok(mut file) = fs::OpenOptions::new().create(true).append(true).open(log_path) {
+        let _ = io::Write::write_all(&mut file, format!("{} {msg}\r\n", Local::now().format("%Y-%m-%d %H:%M:%S%.3f")).as_bytes());
+    }
+}
+
+fn get_colorref(rgb: &str) -> COLORREF {
+    let r: u32 = u32::from_str_radix(&rgb[0..2], 16).unwrap();
+    let g: u32 = u32::from_str_radix(&rgb[2..4], 16).unwrap();
+    let b: u32 = u32::from_str_radix(&rgb[4..6], 16).unwrap();
+    COLORREF((b << 16) + (g << 8) + r)
+}
+
+static GLOBAL_HFONT_HM: Lazy<Mutex<HashMap<String, HFONT>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+fn create_global_hfont(key: String, name: String, size: i32, bold: i32, italic: i32) {
+    unsafe {
+        if !GLOBAL_HFONT_HM.lock().unwrap().contains_key(&key) {
+            let font_name = convert_utf16_null(&name);
+            let font_weight = match bold {
+                1 => FW_BOLD.0,
+                _ => FW_REGULAR.0
+            };
+            let font_italic = match italic {
+                1 => 1,
+                _ => 0
+            };
+            let hfont = CreateFontW(
+                size,
+                0,
+                0,
+                0,
+                font_weight as i32,
+                font_italic,
+                0,
+                0,
+                DEFAULT_CHARSET.0 as u32,
+                OUT_DEFAULT_PRECIS.0 as u32,
+                CLIP_DEFAULT_PRECIS.0 as u32,
+                ANTIALIASED_QUALITY.0 as u32,
+                FIXED_PITCH.0 as u32,
+                PCWSTR(font_name.as_ptr())
+            );
+            GLOBAL_HFONT_HM.lock().unwrap().insert(key, hfont);
+        }
+    }
+}
+
+static GLOBAL_HPEN_HM: Lazy<Mutex<HashMap<String, HPEN>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+fn create_global_hpen(key: &str, color: &str) {
+    unsafe {
+        if !GLOBAL_HPEN_HM.lock().unwrap().contains_key(key) {
+            let hpen = CreatePen(PS_SOLID , 0, get_colorref(color));
+            GLOBAL_HPEN_HM.lock().unwrap().insert(key.to_string(), hpen);
+        }
+    }
+}
+
+#[derive(Default, Debug, Clone)]
+struct TccPanelInfo {
+    global_tcc_display_id: String,
+    panel_id: String,
+    show_desktop_button: i32,
+    show_desktop_button_visible: bool,
+}
+
+static GLOBAL_TCC_PANEL_INFO: Lazy<Mutex<HashMap<isize, TccPanelInfo>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn set_global_tcc_panel_info(k: isize, v: TccPanelInfo) {
+    GLOBAL_TCC_PANEL_INFO.lock().unwrap().insert(k, v);
+}
+
+#[derive(Default, Debug)]
+struct TccTaskbarChecker {
+    display_rect: RECT,
+    taskbar_rect: RECT,
+    taskbar_adjust_left: i32,
+    taskbar_adjust_right: i32,
+    taskbar_tray_hwnd: HWND,
+    taskbar_content_hwnd: HWND,
+    taskbar_topmost: bool,
+    taskbar_visible: i32,
+    display_id: String,
+    panel_id_vec: Vec<(String, HWND)>,
+    panel_background_init: bool
+}
+
+#[derive(Default, Debug, Clone)]
+struct TccDisplay {
+    id: String,
+    zoom: f64,
+    device_name: String,
+    device_path: String,
+    display_rect: RECT,
+    taskbar_tray_hwnd: HWND,
+    taskbar_content_hwnd: HWND,
+    taskbar_rect: RECT,
+    taskbar_adjust_left: i32,
+    taskbar_adjust_right: i32,
+    panels: HashMap<String, TccPanel>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct TccPanel {
+    id: String,
+    hwnd: HWND,
+    position: i32,  // 0:left 1:center 2:right
+    width: i32,
+    left: i32,
+    show_desktop_button_position: i32,
+    labels: Vec<TccLabel>,
+    background_init: bool,
+    background_dc: CreatedHDC,
+    background_bm: HBITMAP,
+}
+
+#[derive(Default, Debug, Clone)]
+struct TccLabel {
+    id: String,
+    timezone: timezone::TccTimezone,
+    format: String,
+    left: i32,
+    top: i32,
+    font_color: String,
+    font_name: String,
+    font_size: i32,
+    font_bold: i32,
+    font_italic: i32,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    business_hours: Option<business::TccBusinessHours>,
+    rules: Vec<TccLabelRule>,
+}
+
+#[derive(Default, Debug, Clone)]
+struct TccLabelRule {
+    condition: condition::TccCondition,
+    font_color: String,
+    // false => 条件に合ったときはラベルを表示しない
+    visible: Option<bool>,
+}
+
+impl TccLabel {
+    fn draw(&mut self, hdc: CreatedHDC, now_utc: DateTime<Utc>) {
+        unsafe {
+            SetBkMode(hdc ,TRANSPARENT);
+        }
+        // timezone
+        let now = now_utc.with_timezone(&self.timezone);
+
+        // rules
+        let mut font_color = &self.font_color;
+        let mut visible = true;
+        for rule in self.rules.iter() {
+            if rule.condition.eval(|placeholder| get_filtered_value(placeholder, &now, self)) {
+                if !rule.font_color.is_empty() {
+                    font_color = &rule.font_color;
+                }
+                if let Some(x) = rule.visible {
+                    visible = x;
+                }
+            }
+        }
+        if !visible {
+            return;
+        }
+
+        unsafe {
+            SetTextColor(hdc, get_colorref(font_color));
+        }
+        create_global_hfont(self.id.to_string(), self.font_name.to_string(), self.font_size, self.font_bold, self.font_italic);
+        let hfont_hm = GLOBAL_HFONT_HM.lock().unwrap();
+        let hfont = hfont_hm.get(&self.id).unwrap();
+        unsafe {
+            SelectObject(hdc, *hfont);
+        }
+
+        // custom format, strftime
+        // https://docs.rs/chrono/latest/chrono/format/strftime/index.html
+        let text = filter::format_label(
+            &self.format,
+            &now,
+            takes_format,
+            |placeholder| get_filtered_value(placeholder, &now, self),
+            |placeholder, err| write_log_once(&format!("[placeholder] {{{placeholder}}} {err}")),
+        );
+
+        unsafe {
+            TextOutW(
+                hdc,
+                self.left,
+                self.top,
+                &convert_utf16(&text)
+            );
+        }
+    }
+}
+
+// 同じエラーは1回だけ tcc-win11.log に書く  (毎秒描画するので、メッセージに値を入れない)
+static GLOBAL_LOGGED_ERROR: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+fn write_log_once(msg: &str) {
+    let mut global_logged_error = GLOBAL_LOGGED_ERROR.lock().unwrap();
+    if !global_logged_error.iter().any(|x| x == msg) {
+        global_logged_error.push(msg.to_string());
+        write_log(msg);
+    }
+}
+
+// placeholder value => filters  (フィルタが失敗したらログに書いて、フィルタ前の値)
+fn get_filtered_value(placeholder: &filter::TccPlaceholder, now: &DateTime<timezone::TccTimezone>, label: &TccLabel) -> String {
+    let value = get_placeholder_value(placeholder, now, label);
+    match filter::apply_filters(value.clone(), &placeholder.filters) {
+        Err(err) => {
+            write_log_once(&format!("[filter] {{{}}} {err}", placeholder.name));
+            value
+        }
+        std::result::Result::Ok(x) => x,
+    }
+}
+
+// {name|format}  "|" の次がフィルタでなければ format として get_placeholder_value に渡す placeholder
+//   {until:release|%D日}  {sunrise|%H時%M分}
+fn takes_format(name: &str) -> bool {
+    name.starts_with("until:") || name.starts_with("since:") || matches!(
+        name,
+        "sunrise" | "sunset" | "daylength" | "jd" | "mjd" | "tai" | "gps" | "gmst" | "lmst"
+            | "tz_diff" | "next_dst_change" | "until_dst_change" | "opens_in" | "closes_in" | "battery_time"
+            | "uptime" | "boot_time"
+    )
+}
+
+fn get_placeholder_value(placeholder: &filter::TccPlaceholder, now: &DateTime<timezone::TccTimezone>, label: &TccLabel) -> String {
+    let custom_format_id = placeholder.name.as_str();
+    match custom_format_id {
+        "holiday" => {
+            let global_tcc_holiday = GLOBAL_TCC_HOLIDAY.lock().unwrap();
+            global_tcc_holiday.get_holiday_name(now.date_naive()).unwrap_or("").to_string()
+        }
+        "kyureki" | "kyureki_year" | "kyureki_month" | "kyureki_day" | "rokuyo" => {
+            let kyureki = koyomi::get_kyureki(now.date_naive(), now.offset().fix().local_minus_utc());
+            match custom_format_id {
+                "kyureki_year" => kyureki.year.to_string(),
+                "kyureki_month" => kyureki.month.to_string(),
+                "kyureki_day" => kyureki.day.to_string(),
+                "rokuyo" => koyomi::get_rokuyo(&kyureki).to_string(),
+                _ => kyureki.to_string_jp(),
+            }
+        }
+        "sekki" | "sekki_day" => {
+            let (sekki, is_sekki_day) = koyomi::get_sekki(now.date_naive(), now.offset().fix().local_minus_utc());
+            if custom_format_id == "sekki" || is_sekki_day {
+                sekki.to_string()
+            } else {
+                "".to_string()
+            }
+        }
+        "kanshi" => koyomi::get_kanshi_year(now.year()),
+        "kanshi_day" => koyomi::get_kanshi_day(now.date_naive()),
+        "eto" => koyomi::get_eto(now.year()).to_string(),
+        "sunrise" | "sunset" | "daylength" => {
+            let (Some(latitude), Some(longitude)) = (label.latitude, label.longitude) else {
+                return "".to_string();
+            };
+            let sun_rise_set = astro::sun_rise_set(now.date_naive(), now.offset().fix().local_minus_utc(), latitude, longitude);
+            if custom_format_id == "daylength" {
+                let seconds = match sun_rise_set {
+                    astro::TccSunRiseSet::Normal(rise, set) => (set - rise).num_seconds(),
+                    astro::TccSunRiseSet::PolarDay => 86400,
+                    astro::TccSunRiseSet::PolarNight => 0,
+                };
+                let fmt = if placeholder.format.is_empty() { "%h:%M" } else { &placeholder.format };
+                return duration::format_duration(seconds, fmt);
+            }
+            match sun_rise_set {
+                astro::TccSunRiseSet::Normal(rise, set) => {
+                    let x = if custom_format_id == "sunrise" { rise } else { set };
+                    let fmt = if placeholder.format.is_empty() { "%H:%M" } else { &placeholder.format };
+                    format_strftime(&x.with_timezone(&now.timezone()), fmt).unwrap_or_default()
+                }
+                // polar day / polar night
+                _ => "--:--".to_string(),
+            }
+        }
+        "jd" | "mjd" => {
+            // {jd|2} => decimal places
+            let jd = astro::julian_day(now.with_timezone(&Utc));
+            let jd = if custom_format_id == "mjd" { timescale::modified_julian_day(jd) } else { jd };
+            let precision = placeholder.format.trim().parse::<usize>().unwrap_or(5);
+            format!("{jd:.precision$}")
+        }
+        "unix" => now.timestamp().to_string(),
+        "unix_ms" => now.timestamp_millis().to_string(),
+        "tai" | "gps" => {
+            let x = if custom_format_id == "tai" { timescale::to_tai(now.with_timezone(&Utc)) } else { timescale::to_gps(now.with_timezone(&Utc)) };
+            let fmt = if placeholder.format.is_empty() { "%H:%M:%S" } else { &placeholder.format };
+            format_strftime(&Utc.from_utc_datetime(&x), fmt).unwrap_or_default()
+        }
+        "tai_utc" => timescale::tai_minus_utc(now.with_timezone(&Utc)).to_string(),
+        "gps_week" => timescale::gps_week(now.with_timezone(&Utc)).0.to_string(),
+        "gps_tow" => timescale::gps_week(now.with_timezone(&Utc)).1.to_string(),
+        "gmst" | "lmst" => {
+            let jd = astro::julian_day(now.with_timezone(&Utc));
+            let degree = match (custom_format_id, label.longitude) {
+                ("gmst", _) => astro::greenwich_mean_sidereal_time(jd),
+                (_, Some(longitude)) => astro::local_mean_sidereal_time(jd, longitude),
+                _ => return "".to_string(),
+            };
+            let fmt = if placeholder.format.is_empty() { "%H:%M:%S" } else { &placeholder.format };
+            duration::format_duration((degree * 240.0).floor() as i64, fmt)
+        }
+        "tz_name" => label.timezone.get_name(),
+        "tz_offset" => now.format("%:z").to_string(),
+        "tz_abbr" => now.format("%Z").to_string(),
+        "is_dst" => if timezone::is_dst(now.offset()) { "1".to_string() } else { "0".to_string() },
+        "tz_diff" => {
+            // difference from local time  +8:00
+            let local_offset = now.with_timezone(&timezone::TccTimezone::Local).offset().fix();
+            let seconds = now.offset().fix().local_minus_utc() - local_offset.local_minus_utc();
+            let fmt = if placeholder.format.is_empty() { "%+%h:%M" } else { &placeholder.format };
+            duration::format_duration(seconds as i64, fmt)
+        }
+        "next_dst_change" | "until_dst_change" => {
+            let Some(next) = label.timezone.get_next_offset_change(now.with_timezone(&Utc)) else {
+                return "".to_string();
+            };
+            if custom_format_id == "until_dst_change" {
+                return duration::format_duration(next.timestamp() - now.timestamp(), &placeholder.format);
+            }
+            let fmt = if placeholder.format.is_empty() { "%Y-%m-%d %H:%M" } else { &placeholder.format };
+            format_strftime(&next.with_timezone(&now.timezone()), fmt).unwrap_or_default()
+        }
+        "open" | "opens_in" | "closes_in" => {
+            let Some(business_hours) = &label.business_hours else {
+                return "".to_string();
+            };
+            let is_open = business_hours.is_open(now);
+            match custom_format_id {
+                "open" => if is_open { "1".to_string() } else { "0".to_string() },
+                // opens_in : closed only, closes_in : open only
+                _ if (custom_format_id == "closes_in") != is_open => "".to_string(),
+                _ => match business_hours.get_next_change(now) {
+                    None => "".to_string(),
+                    Some(next) => duration::format_duration(next.timestamp() - now.timestamp(), &placeholder.format),
+                },
+            }
+        }
+        "fy" | "fq" | "fy_week" => {
+            let fiscal_year = GLOBAL_TCC_FISCAL_YEAR.lock().unwrap();
+            match custom_format_id {
+                "fq" => fiscal_year.get_quarter(now.date_naive()).to_string(),
+                "fy_week" => fiscal_year.get_week(now.date_naive()).to_string(),
+                _ => fiscal_year.get_year(now.date_naive()).to_string(),
+            }
+        }
+        "iso_week" => now.iso_week().week().to_string(),
+        "iso_year" => now.iso_week().year().to_string(),
+        "ordinal" => now.format("%Y-%j").to_string(),
+        "battery_time" => {
+            // discharging only
+            match metric::get_metric_value("battery_time") {
+                Some(x) if !x.is_nan() => {
+                    let fmt = if placeholder.format.is_empty() { "%h:%M" } else { &placeholder.format };
+                    duration::format_duration(x.round() as i64, fmt)
+                }
+                _ => "".to_string(),
+            }
+        }
+        "uptime" | "boot_time" => {
+            // boot_time : unix seconds
+            let Some(boot_time) = metric::get_metric_value("boot_time").filter(|x| !x.is_nan()) else {
+                return "".to_string();
+            };
+            if custom_format_id == "uptime" {
+                let fmt = if placeholder.format.is_empty() { "%Dd %H:%M" } else { &placeholder.format };
+                return duration::format_duration(now.timestamp() - boot_time as i64, fmt);
+            }
+            match Utc.timestamp_opt(boot_time as i64, 0).single() {
+                Some(x) => {
+                    let fmt = if placeholder.format.is_empty() { "%Y-%m-%d %H:%M" } else { &placeholder.format };
+                    format_strftime(&x.with_timezone(&now.timezone()), fmt).unwrap_or_default()
+                }
+                None => "".to_string(),
+            }
+        }
+        "moon_age" => format!("{:.1}", astro::moon_age(astro::julian_day(now.with_timezone(&Utc)))),
+        "moon_phase" => astro::moon_phase_name(astro::moon_age(astro::julian_day(now.with_timezone(&Utc)))).to_string(),
+        _ => {
+            // cpu, _cpu, 0cpu, -cpu, gpu0, mem, ...
+            let metric_unit = GLOBAL_TCC_METRIC_UNIT.lock().unwrap().clone();
+            if let Some(x) = metric::get_metric_text(custom_format_id, &metric_unit) {
+                return x;
+            }
+            // {gpu:name~'RTX'}, {-gpu:dgpu:temp}  => gpu0, -gpu0_temp
+            let (prefix, base) = metric::split_prefix(custom_format_id);
+            let gpu_name = gpu::resolve_gpu_name(base, &GLOBAL_TCC_GPU.lock().unwrap().aliases, &gpu::GLOBAL_GPU_ADAPTER.lock().unwrap());
+            if let Some(gpu_name) = gpu_name {
+                return gpu_name
+                    .and_then(|x| metric::get_metric_text(&format!("{prefix}{x}"), &metric_unit))
+                    .unwrap_or(metric_unit.missing);
+            }
+            // {until:release|%D日}  {since:2024-01-01T00:00:00+09:00|d}
+            if let Some((kind, target)) = custom_format_id.split_once(':') {
+                if kind == "until" || kind == "since" {
+                    return match get_event_datetime(target, &now.timezone()) {
+                        None => "".to_string(),
+                        Some(target) => {
+                            let seconds = target.timestamp() - now.timestamp();
+                            let seconds = if kind == "until" { seconds } else { -seconds };
+                            duration::format_duration(seconds, &placeholder.format)
+                        }
+                    };
+                }
+            }
+            // strftime  {%A|upper}
+            if custom_format_id.starts_with('%') {
+                return format_strftime(now, custom_format_id).unwrap_or_default();
+            }
+            let tcc_custom_format = match GLOBAL_TCC_CUSTOM_FORMAT.lock().unwrap().get(custom_format_id) {
+                None => {
+                    return "".to_string();
+                }
+                Some(x) => x.clone(),
+            };
+            let replace_source = match &tcc_custom_format.source {
+                None => {
+                    format_strftime(now, &tcc_custom_format.value).unwrap_or_default()
+                }
+                Some(source) => {
+                    // no circular reference (checked at load config)
+                    get_filtered_value(source, now, label)
+                }
+            };
+            tcc_custom_format.replace(&replace_source)
+        }
+    }
+}
+
+static GLOBAL_TCC_DISPLAY:Lazy<Mutex<HashMap<String, TccDisplay>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn set_global_tcc_display(k: String, v: TccDisplay) {
+    GLOBAL_TCC_DISPLAY.lock().unwrap().insert(k, v);
+}
+
+// placeholder names used by label (format, rules), including custom_formats > source
+fn get_placeholder_names(label: &TccLabel) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut label_names: Vec<String> = Vec::new();
+    for caps in filter::GLOBAL_PLACEHOLDER_REGEX.captures_iter(&label.format) {
+        if let std::result::Result::Ok(x) = filter::parse_placeholder(&caps[1], takes_format) {
+            label_names.push(x.name);
+        }
+    }
+    for rule in label.rules.iter() {
+        label_names.extend(rule.condition.get_placeholder_names());
+    }
+    let global_tcc_custom_format_hm = GLOBAL_TCC_CUSTOM_FORMAT.lock().unwrap();
+    for mut name in label_names {
+        while !names.contains(&name) {
+            names.push(name.clone());
+            match global_tcc_custom_format_hm.get(&name).and_then(|x| x.source.as_ref()) {
+                None => break,
+                Some(source) => {
+                    name = source.name.clone();
+                }
+            }
+        }
+    }
+    names
+}
+
+static GLOBAL_TCC_CUSTOM_FORMAT: Lazy<Mutex<HashMap<String, custom_format::TccCustomFormat>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static GLOBAL_TCC_EVENT: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// event name or datetime
+fn get_event_datetime<T: TimeZone>(target: &str, tz: &T) -> Option<DateTime<Utc>> {
+    match GLOBAL_TCC_EVENT.lock().unwrap().get(target) {
+        Some(datetime) => duration::parse_datetime(datetime, tz),
+        None => duration::parse_datetime(target, tz),
+    }
+}
+
+static GLOBAL_TCC_HOLIDAY: Lazy<Mutex<holiday::TccHolidayCalendar>> = Lazy::new(|| Mutex::new(holiday::TccHolidayCalendar::default()));
+
+// config.txt > metrics  "cpu": { "interval": 2, "smoothing": "average", "seconds": 10 }
+static GLOBAL_TCC_METRIC_SETTING: Lazy<Mutex<HashMap<String, metric::TccMetricSetting>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// config.txt > network  "adapter": "Wi-Fi", "unit": "bits"
+static GLOBAL_TCC_NETWORK: Lazy<Mutex<network::TccNetworkSetting>> = Lazy::new(|| Mutex::new(network::TccNetworkSetting::default()));
+static GLOBAL_TCC_PROBE: Lazy<Mutex<probe::TccProbeSetting>> = Lazy::new(|| Mutex::new(probe::TccProbeSetting::default()));
+
+// config.txt > gpu  "aggregation": "max", "aliases": { "dgpu": "name~RTX" }
+static GLOBAL_TCC_GPU: Lazy<Mutex<gpu::TccGpuSetting>> = Lazy::new(|| Mutex::new(gpu::TccGpuSetting::default()));
+
+static GLOBAL_TCC_METRIC_UNIT: Lazy<Mutex<metric::TccMetricUnit>> = Lazy::new(|| Mutex::new(metric::TccMetricUnit::default()));
+
+// config.txt > timezone_aliases  "HQ": "Europe/Berlin"
+static GLOBAL_TCC_TIMEZONE_ALIAS: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static GLOBAL_TCC_FISCAL_YEAR: Lazy<Mutex<fiscal::TccFiscalYear>> = Lazy::new(|| Mutex::new(fiscal::TccFiscalYear::default()));
+
+// config.txt > defaults  (used when label does not have the value)
+#[derive(Default, Debug, Clone)]
+struct TccDefaults {
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+}
+
+static GLOBAL_TCC_DEFAULTS: Lazy<Mutex<TccDefaults>> = Lazy::new(|| Mutex::new(TccDefaults::default()));
+
+// latitude : -90 to 90, longitude : -180 to 180
+fn get_config_location(config: &serde_json::Value) -> anyhow::Result<(Option<f64>, Option<f64>)> {
+    let mut location = (None, None);
+    for (key, max, value) in [("latitude", 90.0, &mut location.0), ("longitude", 180.0, &mut location.1)] {
+        match &config[key] {
+            serde_json::Value::Null => {}
+            config_value => match config_value.as_f64() {
+                Some(x) if (-max..=max).contains(&x) => {
+                    *value = Some(x);
+                }
+                _ => {
+                    return Err(anyhow!(format!("{key} => -{max} to {max}")));
+                }
+            },
+        }
+    }
+    Ok(location)
+}
+
+
+static GLOBAL_HWND: Lazy<Mutex<HashMap<String, HWND>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn set_global_hwnd(k: String, v: HWND) {
+    GLOBAL_HWND.lock().unwrap().insert(k, v);
+}
+
+static mut GLOBAL_END: bool = false;
+static mut GLOBAL_UTC_NOW: Lazy<DateTime<Utc>> = Lazy::new(|| Utc::now());
+static mut GLOBAL_MENU_HANDLE: Lazy<HMENU> = Lazy::new(|| HMENU::default());
+
+pub fn main() -> anyhow::Result<()> {
+    unsafe {
+
+        // wait for reload
+        let args: Vec<String> = env::args().collect();
+        if args.len() == 2 {
+            let pid: u32 = args.get_unchecked(1).parse().unwrap(); 
+            match Win32::System::Threading::OpenProcess(
+                PROCESS_ACCESS_RIGHTS(0x00100000),
+                FALSE,
+                pid
+            ) {
+                std::result::Result::Ok(h) => {
+                    let ret = WaitForSingleObject(h.0, 10 * 1000);
+                    if ret == WAIT_TIMEOUT.0 {
+                        println!("error 001");
+                    }
+                },
+                Err(_) => {
+                    println!("error 002");
+                },
+            }
+        }
+
+        // right click menu
+        *GLOBAL_MENU_HANDLE = CreatePopupMenu().unwrap();
+        AppendMenuW(
+            *GLOBAL_MENU_HANDLE,
+            MF_STRING,
+            2,
+            PCWSTR(convert_utf16_null("Reload").as_ptr())
+        );
+        AppendMenuW(
+            *GLOBAL_MENU_HANDLE,
+            MF_STRING,
+            1,
+            PCWSTR(convert_utf16_null("Exit").as_ptr())
+        );
+    
+        // load config
+        let mut config_path = env::current_exe().unwrap();
+        config_path.pop();
+        config_path.push("config.txt");
+        let mut config: serde_json::Value = match fs::read_to_string(config_path) {
+            Err(err) => {
+                error_messagebox("file open config.txt", &err.to_string());
+                panic!("{}", err.to_string())
+            }
+            std::result::Result::Ok(read_text) => {
+                match serde_json::from_str(&read_text.replace("\\", "\\\\")) {
+                    Err(err) => {
+                        error_messagebox("load config.txt", &err.to_string());
+                        panic!("{}", err.to_string())
+                    }
+                    std::result::Result::Ok(v) => {v}
+                }
+            }
+        };
+
+        if config["custom_formats"] != serde_json::Value::Null && config["custom_formats"].is_array() {
+            let mut global_tcc_custom_format_hm = GLOBAL_TCC_CUSTOM_FORMAT.lock().unwrap();
+            for config_custom_format in config["custom_formats"].as_array_mut().unwrap().iter() {
+                let mut tcc_custom_format = custom_format::TccCustomFormat::default();
+                match &config_custom_format["spec"] {
+                    serde_json::Value::Null => {
+                        continue;
+                    }
+                    value => {
+                        let s = value.as_str().unwrap();
+                        if s == "" {
+                            continue;
+                        } else {
+                            tcc_custom_format.spec = s.to_string();
+                        }
+                    }
+                }
+                match &config_custom_format["source"] {
+                    serde_json::Value::Null => {}
+                    value => {
+                        match value.as_str().map(|x| filter::parse_placeholder(x, takes_format)) {
+                            Some(std::result::Result::Ok(x)) if !x.name.is_empty() => {
+                                tcc_custom_format.source = Some(x);
+                            }
+                            _ => {
+                                let msg = format!("[incorrect value] config.txt custom_formats > {} > source", tcc_custom_format.spec);
+                                error_messagebox("load config.txt", &msg);
+                                return Err(anyhow!(msg));
+                            }
+                        }
+                    }
+                }
+                match &config_custom_format["value"] {
+                    serde_json::Value::Null => {
+                        if tcc_custom_format.source.is_none() {
+                            continue;
+                        }
+                    }
+                    value => {
+                        let s = value.as_str().unwrap();
+                        if s == "" {
+                            if tcc_custom_format.source.is_none() {
+                                continue;
+                            }
+                        } else {
+                            tcc_custom_format.value = s.to_string();
+                        }
+                    }
+                }
+                match &config_custom_format["replace"] {
+                    serde_json::Value::Null => {
+                        continue;
+                    }
+                    value => {
+                        // config.txt の順  (範囲が重なったら先に書いた方)
+                        for tpl in value.as_object().unwrap().iter() {
+                            if !tcc_custom_format.items.iter().any(|x| x.0 == *tpl.0) {
+                                tcc_custom_format.items.push((tpl.0.to_string() , tpl.1.as_str().unwrap().to_string()));
+                            }
+                        }
+                    }
+                }
+                if !global_tcc_custom_format_hm.contains_key(&tcc_custom_format.spec) {
+                    global_tcc_custom_format_hm.insert(tcc_custom_format.spec.clone(), tcc_custom_format);
+                }
+            }
+            if let Err(err) = custom_format::check_custom_format_source(&global_tcc_custom_format_hm) {
+                error_messagebox("load config.txt", &err.to_string());
+                return Err(err);
+            }
+        }
+
+        if config["events"] != serde_json::Value::Null && config["events"].is_array() {
+            let mut global_tcc_event_hm = GLOBAL_TCC_EVENT.lock().unwrap();
+            for (i, config_event) in config["events"].as_array().unwrap().iter().enumerate() {
+                let name = config_event["name"].as_str().unwrap_or("");
+                let datetime = config_event["datetime"].as_str().unwrap_or("");
+                if name.is_empty() || duration::parse_datetime(datetime, &Utc).is_none() {
+                    let msg = format!("[incorrect value] config.txt events[{i}] => \"name\", \"datetime\" : \"2026-12-31T00:00:00+09:00\"");
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+                global_tcc_event_hm.insert(name.to_string(), datetime.to_string());
+            }
+        }
+
+        if config["holiday_files"] != serde_json::Value::Null && config["holiday_files"].is_array() {
+            let mut global_tcc_holiday = GLOBAL_TCC_HOLIDAY.lock().unwrap();
+            for config_holiday_file in config["holiday_files"].as_array().unwrap().iter() {
+                let mut holiday_path = env::current_exe().unwrap();
+                holiday_path.pop();
+                holiday_path.push(config_holiday_file.as_str().unwrap_or(""));
+                if let Err(err) = global_tcc_holiday.load_file(&holiday_path) {
+                    error_messagebox("load holiday_files", &err.to_string());
+                    return Err(err);
+                }
+            }
+        }
+
+        if config["metrics"] != serde_json::Value::Null {
+            let mut global_tcc_metric_setting_hm = GLOBAL_TCC_METRIC_SETTING.lock().unwrap();
+            for (name, config_metric) in config["metrics"].as_object().into_iter().flatten() {
+                let mut metric_setting = metric::TccMetricSetting::default();
+                let config_value = &config_metric["interval"];
+                if *config_value != serde_json::Value::Null {
+                    match config_value.as_u64() {
+                        Some(x) if x >= 1 => {
+                            metric_setting.interval = Some(x);
+                        }
+                        _ => {
+                            let msg = format!("[incorrect value] config.txt metrics > {name} > interval => 1, 2, 3, ... (seconds)");
+                            error_messagebox("load config.txt", &msg);
+                            return Err(anyhow!(msg));
+                        }
+                    }
+                }
+                let seconds = config_metric["seconds"].as_u64().unwrap_or(metric_setting.interval.unwrap_or(1) * 5) as usize;
+                match metric::TccSmoothing::parse(config_metric["smoothing"].as_str().unwrap_or(""), seconds) {
+                    Some(x) => {
+                        metric_setting.smoothing = x;
+                    }
+                    None => {
+                        let msg = format!("[incorrect value] config.txt metrics > {name} > smoothing => \"none\", \"average\", \"ema\", \"peak\"");
+                        error_messagebox("load config.txt", &msg);
+                        return Err(anyhow!(msg));
+                    }
+                }
+                global_tcc_metric_setting_hm.insert(name.to_string(), metric_setting);
+            }
+        }
+
+        if config["network"] != serde_json::Value::Null {
+            let mut global_tcc_network = GLOBAL_TCC_NETWORK.lock().unwrap();
+            global_tcc_network.adapter = config["network"]["adapter"].as_str().unwrap_or("").to_string();
+            let config_value = &config["network"]["adapter_regex"];
+            if *config_value != serde_json::Value::Null {
+                match config_value.as_str().map(Regex::new) {
+                    Some(std::result::Result::Ok(x)) => {
+                        global_tcc_network.adapter_regex = Some(x);
+                    }
+                    _ => {
+                        let msg = "[incorrect value] config.txt network > adapter_regex => regular expression".to_string();
+                        error_messagebox("load config.txt", &msg);
+                        return Err(anyhow!(msg));
+                    }
+                }
+            }
+            global_tcc_network.include_virtual = config["network"]["include_virtual"].as_bool().unwrap_or(false);
+            match config["network"]["unit"].as_str() {
+                None | Some("bytes") => {}
+                Some("bits") => {
+                    GLOBAL_TCC_METRIC_UNIT.lock().unwrap().bits = true;
+                }
+                _ => {
+                    let msg = "[incorrect value] config.txt network > unit => \"bytes\", \"bits\"".to_string();
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+            }
+        }
+
+        if config["probe"] != serde_json::Value::Null {
+            let mut global_tcc_probe = GLOBAL_TCC_PROBE.lock().unwrap();
+            let config_value = &config["probe"]["interval"];
+            if *config_value != serde_json::Value::Null {
+                match config_value.as_u64() {
+                    Some(x) if x >= 1 => {
+                        global_tcc_probe.interval = x;
+                    }
+                    _ => {
+                        let msg = "[incorrect value] config.txt probe > interval => 1, 2, 3, ... (seconds)".to_string();
+                        error_messagebox("load config.txt", &msg);
+                        return Err(anyhow!(msg));
+                    }
+                }
+            }
+            let config_value = &config["probe"]["timeout"];
+            if *config_value != serde_json::Value::Null {
+                match config_value.as_u64() {
+                    Some(x) if x >= 1 => {
+                        global_tcc_probe.timeout = x;
+                    }
+                    _ => {
+                        let msg = "[incorrect value] config.txt probe > timeout => 1, 2, 3, ... (milliseconds)".to_string();
+                        error_messagebox("load config.txt", &msg);
+                        return Err(anyhow!(msg));
+                    }
+                }
+            }
+        }
+
+        if config["gpu"] != serde_json::Value::Null {
+            let mut global_tcc_gpu = GLOBAL_TCC_GPU.lock().unwrap();
+            match config["gpu"]["aggregation"].as_str() {
+                None | Some("max") => {}
+                Some("sum") => {
+                    global_tcc_gpu.aggregation = gpu::TccGpuAggregation::Sum;
+                }
+                _ => {
+                    let msg = "[incorrect value] config.txt gpu > aggregation => \"max\", \"sum\"".to_string();
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+            }
+            for (name, config_value) in config["gpu"]["aliases"].as_object().into_iter().flatten() {
+                let value = config_value.as_str().unwrap_or("");
+                if gpu::TccGpuSelector::parse(value).is_none() {
+                    let msg = format!("[incorrect value] config.txt gpu > aliases > {name} => \"name~RTX\", \"name=...\", \"luid=0x00000000_0x0000D1A2\"");
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+                global_tcc_gpu.aliases.insert(name.to_string(), value.to_string());
+            }
+        }
+
+        if config["missing_value"] != serde_json::Value::Null {
+            match config["missing_value"].as_str() {
+                Some(x) => {
+                    GLOBAL_TCC_METRIC_UNIT.lock().unwrap().missing = x.to_string();
+                }
+                None => {
+                    let msg = "[not string] config.txt missing_value".to_string();
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+            }
+        }
+
+        match config["size_unit"].as_str() {
+            None | Some("GiB") => {}
+            Some("GB") => {
+                GLOBAL_TCC_METRIC_UNIT.lock().unwrap().decimal = true;
+            }
+            _ => {
+                let msg = "[incorrect value] config.txt size_unit => \"GiB\", \"GB\"".to_string();
+                error_messagebox("load config.txt", &msg);
+                return Err(anyhow!(msg));
+            }
+        }
+
+        if config["timezone_aliases"] != serde_json::Value::Null {
+            let mut global_tcc_timezone_alias_hm = GLOBAL_TCC_TIMEZONE_ALIAS.lock().unwrap();
+            if !config["timezone_aliases"].is_object() {
+                let msg = "[not object] config.txt timezone_aliases".to_string();
+                error_messagebox("load config.txt", &msg);
+                return Err(anyhow!(msg));
+            }
+            for (name, config_value) in config["timezone_aliases"].as_object().unwrap().iter() {
+                let value = config_value.as_str().unwrap_or("");
+                if let Err(err) = timezone::parse_timezone(value, &HashMap::new()) {
+                    let msg = format!("[incorrect value] config.txt timezone_aliases > {name} => {err}");
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+                global_tcc_timezone_alias_hm.insert(name.to_string(), value.to_string());
+            }
+        }
+
+        if config["fiscal_year"] != serde_json::Value::Null {
+            let mut global_tcc_fiscal_year = GLOBAL_TCC_FISCAL_YEAR.lock().unwrap();
+            let config_value = &config["fiscal_year"]["start_month"];
+            if *config_value != serde_json::Value::Null {
+                match config_value.as_u64() {
+                    Some(x) if (1..=12).contains(&x) => {
+                        global_tcc_fiscal_year.start_month = x as u32;
+                    }
+                    _ => {
+                        let msg = "[incorrect value] config.txt fiscal_year > start_month => 1 to 12".to_string();
+                        error_messagebox("load config.txt", &msg);
+                        return Err(anyhow!(msg));
+                    }
+                }
+            }
+            let config_value = &config["fiscal_year"]["week_start"];
+            if *config_value != serde_json::Value::Null {
+                match config_value.as_str().map(|x| x.parse::<Weekday>()) {
+                    Some(std::result::Result::Ok(x)) => {
+                        global_tcc_fiscal_year.week_start = x;
+                    }
+                    _ => {
+                        let msg = "[incorrect value] config.txt fiscal_year > week_start => \"Mon\" to \"Sun\"".to_string();
+                        error_messagebox("load config.txt", &msg);
+                        return Err(anyhow!(msg));
+                    }
+                }
+            }
+        }
+
+        if config["defaults"] != serde_json::Value::Null {
+            let mut global_tcc_defaults = GLOBAL_TCC_DEFAULTS.lock().unwrap();
+            match get_config_location(&config["defaults"]) {
+                Err(err) => {
+                    let msg = format!("[incorrect value] config.txt defaults > {err}");
+                    error_messagebox("load config.txt", &msg);
+                    return Err(anyhow!(msg));
+                }
+                std::result::Result::Ok((latitude, longitude)) => {
+                    global_tcc_defaults.latitude = latitude;
+                    global_tcc_defaults.longitude = longitude;
+                }
+            }
+        }
+
+        // init create tcc-win11 window
+        let instance = GetModuleHandleW(None).unwrap();
+        let class_name = convert_utf16_null("tcc_win11_window_class");
+        let wc = WNDCLASSW {
+            hCursor: LoadCursorW(None, IDC_ARROW).unwrap(),
+            hInstance: instance,
+            lpszClassName: PCWSTR(class_name.as_ptr()),
+            style: CS_HREDRAW | CS_VREDRAW,
+            lpfnWndProc: Some(wndproc),
+            ..Default::default()
+        };
+        RegisterClassW(&wc);
+
+        let mut vec_window_hwnd: Vec<HWND> = Vec::new();
+
+        // get all display info
+
+        let p = LPARAM::default();
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(enumerate_callback_get_monitors_info),
+            p
+        );
+
+        let mut path_count: u32 = 0;
+        let mut mode_count: u32 = 0;
+        let ret = GetDisplayConfigBufferSizes(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count,
+            &mut mode_count
+        );
+        if ret != NO_ERROR {
+            error_messagebox("GetDisplayConfigBufferSizes", "");
+            return Err(anyhow!("Error GetDisplayConfigBufferSizes"));
+        }
+
+        let mut path_array:Vec<DISPLAYCONFIG_PATH_INFO> = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+        let mut mode_array:Vec<DISPLAYCONFIG_MODE_INFO> = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+        let ret = QueryDisplayConfig(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count,
+            &mut path_array[0],
+            &mut mode_count,
+            &mut mode_array[0],
+            None
+        );
+        if ret != NO_ERROR {
+            error_messagebox("QueryDisplayConfig", "");
+            return Err(anyhow!("Error QueryDisplayConfig"));
+        }
+
+        for i in 0..path_count {
+            let mut displayconfig_target = DISPLAYCONFIG_TARGET_DEVICE_NAME::default();
+            displayconfig_target.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME;
+            displayconfig_target.header.size = mem::size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32;
+            displayconfig_target.header.adapterId = path_array[i as usize].targetInfo.adapterId;
+            displayconfig_target.header.id = path_array[i as usize].targetInfo.id;
+            let ret = DisplayConfigGetDeviceInfo(&mut displayconfig_target.header);
+            if ret != 0 {
+                continue;
+            }
+            let device_path = displayconfig_target.monitorDevicePath.iter().map(|x| (char::from_u32(*x as u32)).unwrap()).collect::<String>();
+
+            let mut displayconfig_source = DISPLAYCONFIG_SOURCE_DEVICE_NAME::default();
+            displayconfig_source.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME;
+            displayconfig_source.header.size = mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32;
+            displayconfig_source.header.adapterId = path_array[i as usize].targetInfo.adapterId;
+            displayconfig_source.header.id = path_array[i as usize].sourceInfo.id;
+            let ret = DisplayConfigGetDeviceInfo(&mut displayconfig_source.header);
+            if ret != 0 {
+                continue;
+            }
+            let device_name = displayconfig_source.viewGdiDeviceName.iter().map(|x| (char::from_u32(*x as u32)).unwrap()).collect::<String>();
+
+            let mut wk = device_path.clone();
+            wk.remove(0);
+            wk.remove(0);
+            wk.remove(0);
+            wk.remove(0);
+            loop {
+                match wk.pop() {
+                    None => {
+                        break;
+                    }
+                    Some('#') => {
+                        break;
+                    }
+                    Some(_) => {
+                        continue;
+                    }
+                }
+            }
+            wk = wk.replace("#", "\\").to_uppercase();
+
+            {
+                let mut global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+                global_tcc_display_hm.get_mut(&device_name).unwrap().device_path = wk;
+            }
+        }
+
+        // get main display taskbar info
+
+        // get taskbar hwnd
+        let taskbar_classname = convert_utf16_null("Shell_TrayWnd");
+        let taskbar_windowname = convert_utf16_null("");
+        let mut taskbar_hwnd;
+        loop {
+            taskbar_hwnd = FindWindowW(
+                PCWSTR(taskbar_classname.as_ptr()),
+                PCWSTR(taskbar_windowname.as_ptr())
+            );
+            if taskbar_hwnd == HWND::default() {
+                sleep(core::time::Duration::from_millis(1000));
+            } else {
+                break;                
+            }
+        }
+        // get taskbar content hwnd
+        let taskbar_content_hwnd: HWND;
+        loop {
+            EnumChildWindows(
+                taskbar_hwnd,
+                Some(enumerate_callback_get_taskbar_content_hwnd),
+                None
+            );
+            {
+                let mut global_hwnd_hm = GLOBAL_HWND.lock().unwrap();
+                if global_hwnd_hm.contains_key("taskbar_content_hwnd") {
+                    taskbar_content_hwnd = *global_hwnd_hm.get("taskbar_content_hwnd").unwrap();
+                    global_hwnd_hm.clear();
+                    break;
+                } else {
+                    sleep(core::time::Duration::from_millis(1000));
+                }
+            }    
+        }
+
+        // get taskbar rect
+        let mut taskbar_rect = RECT::default();
+        GetWindowRect(taskbar_hwnd, &mut taskbar_rect);
+        {
+            let mut global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+            for ref_global_tcc_display in  global_tcc_display_hm.values_mut() {
+                if ref_global_tcc_display.display_rect.left <= taskbar_rect.left
+                && taskbar_rect.left < ref_global_tcc_display.display_rect.right
+                && ref_global_tcc_display.display_rect.top <= taskbar_rect.top
+                && taskbar_rect.top < ref_global_tcc_display.display_rect.bottom {
+
+                    ref_global_tcc_display.taskbar_tray_hwnd = taskbar_hwnd;
+                    ref_global_tcc_display.taskbar_content_hwnd = taskbar_content_hwnd;
+                    ref_global_tcc_display.taskbar_rect = taskbar_rect;
+
+                    let mut display_target = String::from("");
+                    let mut display_id = "";
+                    let mut config_display = serde_json::Value::Null;
+                    let config_displays = config["displays"].as_array().unwrap();
+                    for c in config_displays {
+                        display_target = ref_global_tcc_display.device_path.to_string();
+                        display_id = &ref_global_tcc_display.device_path;
+                        if c["target"].as_str().unwrap() == display_id {
+                            config_display = c.clone();
+                            break;
+                        }
+                    }
+                    if config_display == serde_json::Value::Null {
+                        for c in config_displays {
+                            display_target = "main".to_string();
+                            display_id = "main";
+                            if c["target"].as_str().unwrap() == display_id {
+                                config_display = c.clone();
+                                break;
+                            }
+                        }
+                    }
+                    if config_display == serde_json::Value::Null {
+                        for c in config_displays {
+                            display_target = "all".to_string();
+                            display_id = "all_0";
+                            if c["target"].as_str().unwrap() == "all" {
+                                config_display = c.clone();
+                                break;
+                            }
+                        }
+                    }
+                    if config_display != serde_json::Value::Null {
+                        ref_global_tcc_display.id = display_id.to_string();
+                        ref_global_tcc_display.taskbar_adjust_left = 0;
+                        ref_global_tcc_display.taskbar_adjust_right = 0;
+                        if config_display["taskbar_adjust"] != serde_json::Value::Null {
+                            let config_value = &config_display["taskbar_adjust"]["left"];
+                            if *config_value != serde_json::Value::Null && config_value.is_i64() {
+                                let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+                                ref_global_tcc_display.taskbar_adjust_left = val.ceil() as i32;
+                            }
+                            let config_value = &config_display["taskbar_adjust"]["right"];
+                            if *config_value != serde_json::Value::Null && config_value.is_i64() {
+                                let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+                                ref_global_tcc_display.taskbar_adjust_right = val.ceil() as i32;
+                            }
+                        }
+
+                        match create_window(wc, ref_global_tcc_display, config_display) {
+                            Err(err) => {
+                                error_messagebox(&format!("create window : target = {display_target}"), &err.to_string());
+                                return Err(err);
+                            }
+                            anyhow::Result::Ok(_) => {
+                                for p in ref_global_tcc_display.panels.iter_mut() {
+                                    vec_window_hwnd.push(p.1.hwnd);
+                                }
+                            }
+                        }
+                    }
+                    break;
+                }
+            }
+
+        }
+
+        // get sub displays taskbar info
+
+        let mut taskbar_hwnd = HWND::default();
+        let mut sub_counter = 0;
+
+        loop {
+            sub_counter += 1;
+            // get taskbar hwnd
+            let taskbar_classname = convert_utf16_null("Shell_SecondaryTrayWnd");
+            taskbar_hwnd = FindWindowExW(
+                None,
+                taskbar_hwnd,
+                PCWSTR(taskbar_classname.as_ptr()),
+                None
+            );
+            match taskbar_hwnd.0 {
+                0 => { break }
+                _ => {}
+            }
+            // get taskbar content hwnd
+            let taskbar_content_hwnd: HWND;
+            loop {
+                EnumChildWindows(
+                    taskbar_hwnd,
+                    Some(enumerate_callback_get_taskbar_content_hwnd),
+                    None
+                );
+                {
+                    let mut global_hwnd_hm = GLOBAL_HWND.lock().unwrap();
+                    if global_hwnd_hm.contains_key("taskbar_content_hwnd") {
+                        taskbar_content_hwnd = *global_hwnd_hm.get("taskbar_content_hwnd").unwrap();
+                        global_hwnd_hm.clear();
+                        break;
+                    } else {
+                        sleep(core::time::Duration::from_millis(1000));
+                    }
+                }    
+            }
+    
+            // get taskbar rect
+            let mut taskbar_rect = RECT::default();
+            GetWindowRect(taskbar_hwnd, &mut taskbar_rect);
+            {
+                let mut global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+                for ref_global_tcc_display in global_tcc_display_hm.values_mut() {
+                    if ref_global_tcc_display.display_rect.left <= taskbar_rect.left
+                    && taskbar_rect.left < ref_global_tcc_display.display_rect.right
+                    && ref_global_tcc_display.display_rect.top <= taskbar_rect.top
+                    && taskbar_rect.top < ref_global_tcc_display.display_rect.bottom {
+
+                        ref_global_tcc_display.taskbar_tray_hwnd = taskbar_hwnd;
+                        ref_global_tcc_display.taskbar_content_hwnd = taskbar_content_hwnd;
+                        ref_global_tcc_display.taskbar_rect = taskbar_rect;
+
+                        let mut display_target = String::from("");
+                        let mut display_id = "".to_string();
+                        let mut config_display = serde_json::Value::Null;
+                        let config_displays = config["displays"].as_array().unwrap();
+                        for c in config_displays {
+                            display_target = ref_global_tcc_display.device_path.to_string();
+                            display_id = ref_global_tcc_display.device_path.clone();
+                            if c["target"].as_str().unwrap() == display_id {
+                                config_display = c.clone();
+                                break;
+                            }
+                        }
+                        if config_display == serde_json::Value::Null {
+                            for c in config_displays {
+                                display_target = "sub".to_string();
+                                display_id = "sub_".to_string() + &sub_counter.to_string();
+                                if c["target"].as_str().unwrap() == "sub" {
+                                    config_display = c.clone();
+                                    break;
+                                }
+                            }
+                        }
+                        if config_display == serde_json::Value::Null {
+                            for c in config_displays {
+                                display_target = "all".to_string();
+                                display_id = "all_".to_string() + &sub_counter.to_string();
+                                if c["target"].as_str().unwrap() == "all" {
+                                    config_display = c.clone();
+                                    break;
+                                }
+                            }
+                        }
+                        if config_display != serde_json::Value::Null {
+                            ref_global_tcc_display.id = display_id;
+                            ref_global_tcc_display.taskbar_adjust_left = 0;
+                            ref_global_tcc_display.taskbar_adjust_right = 0;
+                            if config_display["taskbar_adjust"] != serde_json::Value::Null {
+                                let config_value = &config_display["taskbar_adjust"]["left"];
+                                if *config_value != serde_json::Value::Null && config_value.is_i64() {
+                                    let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+                                    ref_global_tcc_display.taskbar_adjust_left = val.ceil() as i32;
+                                }
+                                let config_value = &config_display["taskbar_adjust"]["right"];
+                                if *config_value != serde_json::Value::Null && config_value.is_i64() {
+                                    let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+                                    ref_global_tcc_display.taskbar_adjust_right = val.ceil() as i32;
+                                }
+                            }
+
+                            match create_window(wc, ref_global_tcc_display, config_display) {
+                                Err(err) => {
+                                    error_messagebox(&format!("create window : target = {display_target}"), &err.to_string());
+                                    return Err(err);
+                                }
+                                anyhow::Result::Ok(_) => {
+                                    for p in ref_global_tcc_display.panels.iter_mut() {
+                                        vec_window_hwnd.push(p.1.hwnd);
+                                    }
+                                }
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+        }
+
+        // metric sources  (only sources used by labels)
+        let mut metric_registry = metric::TccMetricRegistry::default();
+        metric_registry.register("cpu", Box::new(metric_win::TccCpuSource::default()));
+        metric_registry.register("gpu", Box::new(metric_win::TccGpuSource::new(GLOBAL_TCC_GPU.lock().unwrap().clone())));
+        metric_registry.register("mem", Box::new(metric_win::TccMemSource::default()));
+        metric_registry.register("net", Box::new(metric_win::TccNetSource::new(GLOBAL_TCC_NETWORK.lock().unwrap().clone())));
+        metric_registry.register("disk", Box::new(metric_win::TccDiskSpaceSource::default()));
+        metric_registry.register("disk_io", Box::new(metric_win::TccDiskIoSource::default()));
+        metric_registry.register("battery", Box::new(metric_win::TccBatterySource::default()));
+        metric_registry.register("sysinfo", Box::new(metric_win::TccSysInfoSource::default()));
+        metric_registry.register("proc", Box::new(metric_win::TccProcSource::default()));
+        metric_registry.register("probe", Box::new(metric_win::TccProbeSource::new(*GLOBAL_TCC_PROBE.lock().unwrap())));
+        let mut placeholder_names: Vec<String> = Vec::new();
+        {
+            let global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+            for global_tcc_display in global_tcc_display_hm.values() {
+                for panel in global_tcc_display.panels.values() {
+                    for label in panel.labels.iter() {
+                        placeholder_names.extend(get_placeholder_names(label));
+                    }
+                }
+            }
+        }
+        // init (WMI, PDH) は metric thread で行う
+        let mut metric_sampler = metric_registry.build(&placeholder_names, &GLOBAL_TCC_METRIC_SETTING.lock().unwrap());
+
+        // taskbar check thread
+        let mut tcc_taskbar_checker_vec: Vec<TccTaskbarChecker> = Vec::new();
+        {
+            let global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+            for global_tcc_display in global_tcc_display_hm.values() {
+                let mut tcc_taskbar_checker = TccTaskbarChecker::default();
+                tcc_taskbar_checker.display_rect = global_tcc_display.display_rect;
+                tcc_taskbar_checker.taskbar_rect = global_tcc_display.taskbar_rect;
+                tcc_taskbar_checker.taskbar_adjust_left = global_tcc_display.taskbar_adjust_left;
+                tcc_taskbar_checker.taskbar_adjust_right = global_tcc_display.taskbar_adjust_right;
+                tcc_taskbar_checker.taskbar_tray_hwnd = global_tcc_display.taskbar_tray_hwnd;
+                tcc_taskbar_checker.taskbar_content_hwnd = global_tcc_display.taskbar_content_hwnd;
+                tcc_taskbar_checker.taskbar_topmost = false;
+                tcc_taskbar_checker.taskbar_visible = -1;
+
+                tcc_taskbar_checker.display_id = global_tcc_display.device_name.to_string();
+                for v in global_tcc_display.panels.values() {
+                    tcc_taskbar_checker.panel_id_vec.push((v.id.to_string(), v.hwnd));
+                    tcc_taskbar_checker.panel_background_init = v.background_init;
+                }
+                tcc_taskbar_checker_vec.push(tcc_taskbar_checker);
+            }
+        }
+        thread::spawn(move || {
+            loop {
+                for ref_tcc_taskbar_checker in tcc_taskbar_checker_vec.iter_mut() {
+
+                    if !ref_tcc_taskbar_checker.panel_background_init {
+                        let style = GetWindowLongW(ref_tcc_taskbar_checker.taskbar_tray_hwnd, GWL_STYLE) as u32;
+                        let exstyle = GetWindowLongW(ref_tcc_taskbar_checker.taskbar_tray_hwnd, GWL_EXSTYLE) as u32;
+                        let mut rect = RECT::default();
+                        GetWindowRect(ref_tcc_taskbar_checker.taskbar_tray_hwnd, &mut rect);
+                        if (exstyle & WS_EX_TOPMOST.0 == WS_EX_TOPMOST.0) && (style & WS_VISIBLE.0 == WS_VISIBLE.0) && (ref_tcc_taskbar_checker.display_rect.bottom == rect.bottom) && !is_lock_screen() {
+
+                            sleep(core::time::Duration::from_millis(1000));
+
+                            ref_tcc_taskbar_checker.panel_background_init = true;
+
+                            SetWindowPos(
+                                ref_tcc_taskbar_checker.taskbar_content_hwnd,
+                                HWND_BOTTOM,
+                                0,
+                                0,
+                                ref_tcc_taskbar_checker.display_rect.right - ref_tcc_taskbar_checker.display_rect.left,
+                                ref_tcc_taskbar_checker.taskbar_rect.bottom - ref_tcc_taskbar_checker.taskbar_rect.top,
+                                SWP_NOACTIVATE | SWP_NOZORDER | SWP_NOSENDCHANGING | SWP_NOMOVE
+                            );
+
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                ShowWindow(panel_id_hwnd.1, SW_HIDE);
+                            }
+
+                            {
+                                let mut global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+                                match global_tcc_display_hm.get_mut(&ref_tcc_taskbar_checker.display_id) {
+                                    None => {
+                                    }
+                                    Some(global_tcc_display) => {
+                                        for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                            let mut panel_rect = RECT::default();
+                                            GetWindowRect(panel_id_hwnd.1, &mut panel_rect);
+                                            let tcc_panel = global_tcc_display.panels.get_mut(&panel_id_hwnd.0).unwrap();
+                                            let window_height = ref_tcc_taskbar_checker.taskbar_rect.bottom - ref_tcc_taskbar_checker.taskbar_rect.top;
+                                            let hdc = GetDC(tcc_panel.hwnd);
+                                            tcc_panel.background_dc = CreateCompatibleDC(hdc);
+                                            tcc_panel.background_bm = CreateCompatibleBitmap(hdc, tcc_panel.width, window_height);
+                                            SelectObject(tcc_panel.background_dc, tcc_panel.background_bm);
+    
+                                            let desktop_hdc = GetDC(HWND(0));
+                                            for i in 0..window_height {
+                                                let j = match i {
+                                                    0 => 0,
+                                                    _ => window_height - 1
+                                                };
+                                                BitBlt(
+                                                    tcc_panel.background_dc,
+                                                    0,
+                                                    i,
+                                                    tcc_panel.width,
+                                                    1,
+                                                    desktop_hdc,
+                                                    panel_rect.left,
+                                                    panel_rect.top + j,
+                                                    SRCCOPY
+                                                );
+                                            }
+                                            tcc_panel.background_init = true;
+                                        }
+                                    }
+                                }    
+                            }
+
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                ShowWindow(panel_id_hwnd.1, SW_HIDE);
+                            }
+
+                            ref_tcc_taskbar_checker.taskbar_visible = -1;
+                        }
+                    }
+
+                    let exstyle = GetWindowLongW(ref_tcc_taskbar_checker.taskbar_tray_hwnd, GWL_EXSTYLE) as u32;
+                    if exstyle & WS_EX_TOPMOST.0 == 0 {
+                        if ref_tcc_taskbar_checker.taskbar_topmost == true {
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                SetWindowPos(
+                                    panel_id_hwnd.1,
+                                    HWND_BOTTOM,
+                                    0,
+                                    0,
+                                    0,
+                                    0,
+                                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE
+                                );
+                            }
+                        }
+                        ref_tcc_taskbar_checker.taskbar_topmost = false;
+                    } else {
+                        if ref_tcc_taskbar_checker.taskbar_topmost == false {
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                SetWindowPos(
+                                    panel_id_hwnd.1,
+                                    HWND_TOPMOST,
+                                    0,
+                                    0,
+                                    0,
+                                    0,
+                                    SWP_NOMOVE | SWP_NOSIZE
+                                );
+                            }
+                        } else {
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                let mut h = panel_id_hwnd.1;
+                                loop {
+                                    h = GetWindow(h, GW_HWNDPREV);
+                                    if h.0 == 0 {
+                                        break;
+                                    }
+                                    if h == ref_tcc_taskbar_checker.taskbar_tray_hwnd {
+                                        SetWindowPos(
+                                            panel_id_hwnd.1,
+                                            HWND_TOPMOST,
+                                            0,
+                                            0,
+                                            0,
+                                            0,
+                                            SWP_NOMOVE | SWP_NOSIZE
+                                        );
+                                        break;
+                                    }
+                                }
+                            }
+                        }
+                        ref_tcc_taskbar_checker.taskbar_topmost = true;
+                    }
+
+                    let mut rect = RECT::default();
+                    GetWindowRect(ref_tcc_taskbar_checker.taskbar_tray_hwnd, &mut rect);
+                    if ref_tcc_taskbar_checker.display_rect.bottom == rect.bottom {
+                        if ref_tcc_taskbar_checker.taskbar_visible == -1 || ref_tcc_taskbar_checker.taskbar_visible == 0 {
+                            ref_tcc_taskbar_checker.taskbar_visible = 1;
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                ShowWindow(panel_id_hwnd.1, SW_SHOW);
+                            }
+                        }
+                    } else {
+                        if ref_tcc_taskbar_checker.taskbar_visible == -1 || ref_tcc_taskbar_checker.taskbar_visible == 1 {
+                            ref_tcc_taskbar_checker.taskbar_visible = 0;
+                            for panel_id_hwnd in ref_tcc_taskbar_checker.panel_id_vec.iter_mut() {
+                                ShowWindow(panel_id_hwnd.1, SW_HIDE);
+                            }
+                        }
+                    }
+
+                    if ref_tcc_taskbar_checker.taskbar_adjust_left != 0 || ref_tcc_taskbar_checker.taskbar_adjust_right != 0 {
+                        let mut rect = RECT::default();
+                        GetWindowRect(ref_tcc_taskbar_checker.taskbar_content_hwnd, &mut rect);
+                        let taskbar_width = rect.right - rect.left;
+                        let display_width = ref_tcc_taskbar_checker.display_rect.right - ref_tcc_taskbar_checker.display_rect.left;
+                        if taskbar_width == display_width {
+                            SetWindowPos(
+                                ref_tcc_taskbar_checker.taskbar_content_hwnd,
+                                HWND_BOTTOM,
+                                ref_tcc_taskbar_checker.taskbar_adjust_left,
+                                0,
+                                display_width - ref_tcc_taskbar_checker.taskbar_adjust_left + ref_tcc_taskbar_checker.taskbar_adjust_right,
+                                ref_tcc_taskbar_checker.taskbar_rect.bottom - ref_tcc_taskbar_checker.taskbar_rect.top,
+                                SWP_NOACTIVATE | SWP_NOZORDER | SWP_NOSENDCHANGING
+                            );
+                        }
+                    }
+                }
+                sleep(core::time::Duration::from_millis(100));
+            }
+        });
+
+        // metric thread  (slow PDH / WMI calls do not delay the clock)
+        let (metric_thread_channel_sender, metric_thread_channel_receiver) = channel::<i32>();
+        let metric_thread_join_handle = spawn(move || {
+            // 初期化できなかった source は使わない
+            if let Err(err) = metric_sampler.init() {
+                error_messagebox("init metric", &err.to_string());
+            }
+            loop {
+                metric_sampler.sample();
+                let wait = (tick::get_next_second(Utc::now()) - Utc::now()).to_std().unwrap_or_default();
+                match metric_thread_channel_receiver.recv_timeout(wait) {
+                    Err(RecvTimeoutError::Timeout) => (),
+                    _ => break,
+                }
+            }
+            metric_sampler.close();
+        });
+
+        // clock timer thread  (tick on the second boundary)
+        let (clock_thread_channel_sender, clock_thread_channel_receiver) = channel::<i32>();
+        let clock_thread_join_handle = spawn(move || {
+            let mut tick_scheduler = tick::TccTickScheduler::default();
+            loop {
+                // wait for the next second (again if woken early)
+                if let Some(wait) = tick_scheduler.get_wait(Utc::now()) {
+                    match clock_thread_channel_receiver.recv_timeout(wait) {
+                        Err(RecvTimeoutError::Timeout) => continue,
+                        _ => break,
+                    }
+                }
+
+                *GLOBAL_UTC_NOW = Utc::now();
+                match tick_scheduler.start_tick(*GLOBAL_UTC_NOW) {
+                    tick::TccTickStatus::OnTime => (),
+                    tick::TccTickStatus::Late(x) => write_log(&format!("[clock] late tick : {x} ms")),
+                    tick::TccTickStatus::Skipped(x) => write_log(&format!("[clock] skipped tick : {x} s")),
+                }
+
+                // refresh all window
+                for h in vec_window_hwnd.iter() {
+                    InvalidateRect(*h, None, true);
+                    UpdateWindow(*h);
+                }
+            }
+        });
+
+        // message loop
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, HWND(0), 0, 0).into() {
+            TranslateMessage(&mut message);
+            DispatchMessageW(&mut message);
+        }
+
+        clock_thread_channel_sender.send(1).unwrap();
+        let _ = clock_thread_join_handle.join();
+        metric_thread_channel_sender.send(1).unwrap();
+        let _ = metric_thread_join_handle.join();
+
+        // dispose
+        {
+            let global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+            for tcc_display in global_tcc_display_hm.values() {
+                for tcc_panel in tcc_display.panels.values() {
+                    if tcc_panel.background_init {
+                        DeleteObject(tcc_panel.background_bm);
+                        DeleteDC(tcc_panel.background_dc);    
+                    }
+                }
+            }
+        }
+
+        for hfont in GLOBAL_HFONT_HM.lock().unwrap().iter() {
+            DeleteObject(*hfont.1);
+        }
+
+        for hpen in GLOBAL_HPEN_HM.lock().unwrap().iter() {
+            DeleteObject(*hpen.1);
+        }
+
+        DestroyMenu(*GLOBAL_MENU_HANDLE);
+
+        // restore taskbar
+        {
+            let global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+            for global_tcc_display in global_tcc_display_hm.values() {
+                if global_tcc_display.taskbar_adjust_left != 0 || global_tcc_display.taskbar_adjust_right != 0 {
+                    SetWindowPos(
+                        global_tcc_display.taskbar_content_hwnd,
+                        HWND_BOTTOM,
+                        0,
+                        0,
+                        global_tcc_display.taskbar_rect.right - global_tcc_display.taskbar_rect.left,
+                        global_tcc_display.taskbar_rect.bottom - global_tcc_display.taskbar_rect.top,
+                        SWP_NOACTIVATE | SWP_NOZORDER | SWP_NOSENDCHANGING
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn create_window(wc: WNDCLASSW, ref_global_tcc_display: &mut TccDisplay, config_display: serde_json::Value) -> anyhow::Result<()> {
+    let device_name = &ref_global_tcc_display.device_name;
+
+    let class_name = convert_utf16_null("tcc_win11_window_class");
+    let app_title = convert_utf16_null("tcc_win11");
+
+    if config_display["panels"] == serde_json::Value::Null {
+        return Err(anyhow!(format!("[nothing] config.txt displays > panels")));
+    }
+    if !config_display["panels"].is_array() {
+        return Err(anyhow!(format!("[not array] config.txt displays > panels")));
+    }
+    let config_panels = config_display["panels"].as_array().unwrap();
+    for i in 0..config_panels.len() {
+
+        let config_panel = &config_panels[i];
+
+        let mut tcc_panel = TccPanel::default();
+
+        tcc_panel.id = ref_global_tcc_display.id.to_string() + "_" + &i.to_string();
+
+        let key = "position";
+        let config_value = &config_panel[key];
+        if *config_value == serde_json::Value::Null {
+            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > {key}")));
+        }
+        if !config_value.is_string() {
+            return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > {key}")));
+        }
+        tcc_panel.position = match config_value.as_str() {
+            Some("left") => { 0 }
+            Some("center") => { 1 }
+            Some("right") => { 2 }
+            _ => {
+                return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > {key} => \"left\" or \"center\" or \"right\"")));
+            }
+        };
+
+        let key = "width";
+        let config_value = &config_panel[key];
+        if *config_value == serde_json::Value::Null {
+            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > {key}")));
+        }
+        if !config_value.is_i64() {
+            return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > {key}")));
+        }
+        let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+        tcc_panel.width = val.ceil() as i32;
+
+        let key = "left";
+        let config_value = &config_panel[key];
+        if *config_value == serde_json::Value::Null {
+            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > {key}")));
+        }
+        if !config_value.is_i64() {
+            return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > {key}")));
+        }
+        let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+        tcc_panel.left = val.ceil() as i32;
+
+        let key = "show_desktop_button_position";
+        let config_value = &config_panel[key];
+        if *config_value == serde_json::Value::Null {
+            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > {key}")));
+        }
+        if !config_value.is_string() {
+            return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > {key}")));
+        }
+        tcc_panel.show_desktop_button_position = match config_value.as_str() {
+            Some("left") => { 0 }
+            Some("center") => { 1 }
+            Some("right") => { 2 }
+            _ => { -1 }
+        };
+
+        tcc_panel.background_init = false;
+
+        // create panel-window
+        let mut window_x = 0;
+        let mut window_y = 0;
+        let window_height = ref_global_tcc_display.taskbar_rect.bottom - ref_global_tcc_display.taskbar_rect.top; 
+        match tcc_panel.position {
+            0 => {
+                window_x = ref_global_tcc_display.taskbar_rect.left + tcc_panel.left;
+                window_y = ref_global_tcc_display.display_rect.bottom - window_height;
+            }
+            1 => {
+                window_x = (ref_global_tcc_display.taskbar_rect.left + ref_global_tcc_display.display_rect.right) / 2 - tcc_panel.width / 2 + tcc_panel.left;
+                window_y = ref_global_tcc_display.display_rect.bottom - window_height;
+            }
+            2 => {
+                window_x = ref_global_tcc_display.display_rect.right - tcc_panel.width + tcc_panel.left;
+                window_y = ref_global_tcc_display.display_rect.bottom - window_height;
+            }
+            _ => {}
+        }
+        let nullptr: ::core::option::Option<*const ::core::ffi::c_void> = None;
+        unsafe {
+            tcc_panel.hwnd = CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW, // | WS_EX_LAYERED,
+                PCWSTR(class_name.as_ptr()),
+                PCWSTR(app_title.as_ptr()),
+                WS_VISIBLE | WS_POPUP,
+                window_x,
+                window_y,
+                tcc_panel.width,
+                window_height,
+                None,
+                None,
+                wc.hInstance,
+                nullptr,
+            );
+        }
+
+        // labels
+        if config_panel["labels"] == serde_json::Value::Null {
+            return Err(anyhow!(format!("[nothing] config.txt displays > panels[i] > labels")));
+        }
+        if !config_panel["labels"].is_array() {
+            return Err(anyhow!(format!("[not array] config.txt displays > panels[i] > labels")));
+        }
+        let config_labels = config_panel["labels"].as_array().unwrap();
+        for j in 0..config_labels.len() {
+
+            let config_label = &config_labels[j];
+
+            let mut tcc_label = TccLabel::default();
+
+            tcc_label.id = tcc_panel.id.to_string() + "_" + &j.to_string();
+
+            let key = "timezone";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_string() {
+                return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            // "" => local
+            match timezone::parse_timezone(config_value.as_str().unwrap(), &GLOBAL_TCC_TIMEZONE_ALIAS.lock().unwrap()) {
+                Err(err) => {
+                    return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key} => {err}")));
+                }
+                std::result::Result::Ok(x) => {
+                    tcc_label.timezone = x;
+                }
+            }
+
+            // latitude, longitude  (config.txt > defaults)
+            match get_config_location(config_label) {
+                Err(err) => {
+                    return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {err}")));
+                }
+                std::result::Result::Ok((latitude, longitude)) => {
+                    let global_tcc_defaults = GLOBAL_TCC_DEFAULTS.lock().unwrap();
+                    tcc_label.latitude = latitude.or(global_tcc_defaults.latitude);
+                    tcc_label.longitude = longitude.or(global_tcc_defaults.longitude);
+                }
+            }
+
+            let key = "business_hours";
+            let config_value = &config_label[key];
+            if *config_value != serde_json::Value::Null {
+                let mut business_hours = match config_value.as_str().map(business::TccBusinessHours::parse) {
+                    None => {
+                        return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+                    }
+                    Some(Err(err)) => {
+                        return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key} => {err}")));
+                    }
+                    Some(std::result::Result::Ok(x)) => x,
+                };
+                // "japan" => national holidays + holiday_files, "xxx.txt" => holiday file only
+                let key = "business_holidays";
+                let config_value = &config_label[key];
+                if *config_value != serde_json::Value::Null {
+                    match config_value.as_str() {
+                        None => {
+                            return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+                        }
+                        Some("japan") => {
+                            business_hours.national_holidays = true;
+                            business_hours.holidays = GLOBAL_TCC_HOLIDAY.lock().unwrap().clone();
+                        }
+                        Some(config_string) => {
+                            let mut holiday_path = env::current_exe().unwrap();
+                            holiday_path.pop();
+                            holiday_path.push(config_string);
+                            if let Err(err) = business_hours.holidays.load_file(&holiday_path) {
+                                return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key} => {err}")));
+                            }
+                        }
+                    }
+                }
+                tcc_label.business_hours = Some(business_hours);
+            }
+
+            let key = "format";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_string() {
+                return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            tcc_label.format = config_label[key].as_str().unwrap().to_string();
+            for caps in filter::GLOBAL_PLACEHOLDER_REGEX.captures_iter(&tcc_label.format) {
+                match filter::parse_placeholder(&caps[1], takes_format) {
+                    Err(err) => {
+                        return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key} => {err}")));
+                    }
+                    std::result::Result::Ok(placeholder) => {
+                        if let Some(target) = placeholder.name.strip_prefix("until:").or(placeholder.name.strip_prefix("since:")) {
+                            if get_event_datetime(target, &Utc).is_none() {
+                                return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key} => unknown event : {target}")));
+                            }
+                        }
+                        if ["sunrise", "sunset", "daylength"].contains(&placeholder.name.as_str()) && (tcc_label.latitude.is_none() || tcc_label.longitude.is_none()) {
+                            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > latitude, longitude (or defaults) => {{{}}}", placeholder.name)));
+                        }
+                        if ["open", "opens_in", "closes_in"].contains(&placeholder.name.as_str()) && tcc_label.business_hours.is_none() {
+                            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > business_hours => {{{}}}", placeholder.name)));
+                        }
+                        if placeholder.name == "lmst" && tcc_label.longitude.is_none() {
+                            return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > longitude (or defaults) => {{lmst}}")));
+                        }
+                    }
+                }
+            }
+
+            let key = "left";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_i64() {
+                return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+            tcc_label.left = val.ceil() as i32;
+
+            let key = "top";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_i64() {
+                return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+            tcc_label.top = val.ceil() as i32;
+
+            let key = "font_color";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_string() {
+                return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            let str_value = config_value.as_str().unwrap();
+            let color_regex = Regex::new(r"[0-9a-fA-F]{6}").unwrap();
+            if !color_regex.is_match(str_value) {
+                return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key} => \"000000\" to \"FFFFFF\"")));
+            }
+            tcc_label.font_color = str_value.to_string();
+
+            let key = "font_name";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_string() {
+                return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            tcc_label.font_name = config_label[key].as_str().unwrap().to_string();
+
+            let key = "font_size";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_i64() {
+                return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            let val = config_value.as_f64().unwrap() * ref_global_tcc_display.zoom;
+            tcc_label.font_size = val.ceil() as i32;
+
+            let key = "font_bold";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_i64() {
+                return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            tcc_label.font_bold = config_value.as_i64().unwrap() as i32;
+
+            let key = "font_italic";
+            let config_value = &config_label[key];
+            if *config_value == serde_json::Value::Null {
+                return Err(anyhow!(format!("[nothing] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            if !config_value.is_i64() {
+                return Err(anyhow!(format!("[not number] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+            }
+            tcc_label.font_italic = config_value.as_i64().unwrap() as i32;
+
+            let key = "rules";
+            let config_value = &config_label[key];
+            if *config_value != serde_json::Value::Null {
+                if !config_value.is_array() {
+                    return Err(anyhow!(format!("[not array] config.txt displays > panels[{i}] > labels[{j}] > {key}")));
+                }
+                let config_rules = config_value.as_array().unwrap();
+                for (k, config_rule) in config_rules.iter().enumerate() {
+                    let mut tcc_label_rule = TccLabelRule::default();
+                    let config_value = &config_rule["if"];
+                    if !config_value.is_string() {
+                        return Err(anyhow!(format!("[not string] config.txt displays > panels[{i}] > labels[{j}] > {key}[{k}] > if")));
+                    }
+                    tcc_label_rule.condition = match condition::TccCondition::parse(config_value.as_str().unwrap(), takes_format) {
+                        Err(err) => {
+                            return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key}[{k}] > if => {err}")));
+                        }
+                        std::result::Result::Ok(x) => x,
+                    };
+                    let config_value = &config_rule["font_color"];
+                    if *config_value != serde_json::Value::Null {
+                        let str_value = config_value.as_str().unwrap_or("");
+                        if !color_regex.is_match(str_value) {
+                            return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key}[{k}] > font_color => \"000000\" to \"FFFFFF\"")));
+                        }
+                        tcc_label_rule.font_color = str_value.to_string();
+                    }
+                    let config_value = &config_rule["visible"];
+                    if *config_value != serde_json::Value::Null {
+                        if !config_value.is_boolean() {
+                            return Err(anyhow!(format!("[incorrect value] config.txt displays > panels[{i}] > labels[{j}] > {key}[{k}] > visible => true, false")));
+                        }
+                        tcc_label_rule.visible = config_value.as_bool();
+                    }
+                    tcc_label.rules.push(tcc_label_rule);
+                }
+            }
+
+            tcc_panel.labels.push(tcc_label);
+
+        }
+
+        let mut tcc_panel_info = TccPanelInfo::default();
+        tcc_panel_info.global_tcc_display_id = device_name.to_string();
+        tcc_panel_info.panel_id = tcc_panel.id.clone();
+        tcc_panel_info.show_desktop_button = tcc_panel.show_desktop_button_position.clone();
+        tcc_panel_info.show_desktop_button_visible = false;
+        set_global_tcc_panel_info(tcc_panel.hwnd.0, tcc_panel_info);
+
+        ref_global_tcc_display.panels.insert(tcc_panel.id.clone(), tcc_panel);
+
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn enumerate_callback_get_monitors_info(
+    hmonitor: HMONITOR,
+    _: HDC,
+    _: *mut RECT,
+    _: LPARAM,
+) -> BOOL {
+    let mut monitor_info_ex: MONITORINFOEXW = MONITORINFOEXW::default();
+    monitor_info_ex.monitorInfo.cbSize = mem::size_of::<MONITORINFOEXW>() as u32;
+    let monitor_info_ptr = <*mut _>::cast(&mut monitor_info_ex);
+    let result = GetMonitorInfoW(hmonitor, monitor_info_ptr);
+    if result == TRUE {
+        let device_name = monitor_info_ex.szDevice.iter().map(|x| (char::from_u32(*x as u32)).unwrap()).collect::<String>();
+        let mut tcc_display = TccDisplay::default();
+        tcc_display.display_rect = monitor_info_ex.monitorInfo.rcMonitor;
+        tcc_display.device_name = device_name.clone();
+
+        let mut dpi_x = 0u32;
+        let mut dpi_y = 0u32;
+        let _ = GetDpiForMonitor(
+            hmonitor,
+            MDT_EFFECTIVE_DPI,
+            &mut dpi_x,
+            &mut dpi_y
+        );
+        tcc_display.zoom = dpi_x as f64 / 96.0;
+        set_global_tcc_display(device_name, tcc_display);
+    }
+    TRUE
+}
+
+unsafe extern "system" fn enumerate_callback_get_taskbar_content_hwnd(hwnd: HWND, _: LPARAM) -> BOOL {
+    let mut classname:Vec<u8> = vec![0; 100];
+    GetClassNameA(hwnd, &mut classname);
+    let a = String::from_utf8(classname).unwrap();
+    let b = a.trim_end_matches(char::from(0));
+    if b == "Windows.UI.Composition.DesktopWindowContentBridge" {
+        set_global_hwnd(String::from("taskbar_content_hwnd"), hwnd);
+        return FALSE;
+    }
+    return TRUE
+}
+
+extern "system" fn wndproc(hwnd: HWND, message: u32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+    unsafe {
+        match message as u32 {
+            WM_PAINT => {
+
+                let mut rect: RECT = RECT::default();
+                GetClientRect(hwnd, &mut rect);
+
+                let mut ps = PAINTSTRUCT::default();
+                let hdc = BeginPaint(hwnd,  &mut ps);
+
+                let global_tcc_panel_info_hm = GLOBAL_TCC_PANEL_INFO.lock().unwrap();
+                match global_tcc_panel_info_hm.get(&hwnd.0) {
+                    None => {}
+                    Some(global_tcc_panel_info) => {
+                        let mut global_tcc_display_hm = GLOBAL_TCC_DISPLAY.lock().unwrap();
+                        match global_tcc_display_hm.get_mut(&global_tcc_panel_info.global_tcc_display_id) {
+                            None => {}
+                            Some(global_tcc_display) => {
+                                let hdc_mem: CreatedHDC = CreateCompatibleDC(hdc);
+                                let hbm_mem: HBITMAP = CreateCompatibleBitmap(hdc, rect.right, rect.bottom);
+                                SelectObject(hdc_mem, hbm_mem);
+
+                                let tcc_panel = global_tcc_display.panels.get_mut(&global_tcc_panel_info.panel_id).unwrap();
+
+                                // fill backcolor
+                                let mut rect = RECT::default();
+                                GetClientRect(hwnd, &mut rect);
+                                if tcc_panel.background_init {
+                                    BitBlt(
+                                        hdc_mem,
+                                        0,
+                                        0,
+                                        rect.right - rect.left,
+                                        rect.bottom - rect.top,
+                                        tcc_panel.background_dc,
+                                        0,
+                                        0,
+                                        SRCCOPY
+                                    );
+                                }
+
+                                // draw labels
+                                let now_utc: DateTime<Utc> = *GLOBAL_UTC_NOW;
+                                for i in 0..tcc_panel.labels.len() {
+                                    let label = &mut tcc_panel.labels[i];
+                                    label.draw(hdc_mem, now_utc);
+                                }
+
+                                // draw show desktop button
+                                create_global_hpen("white_pen", "FFFFFF");
+                                let hpen_hm = GLOBAL_HPEN_HM.lock().unwrap();
+                                if global_tcc_panel_info.show_desktop_button_visible {
+                                    let hpen = hpen_hm.get("white_pen").unwrap();
+                                    SelectObject(hdc_mem, *hpen);
+                                    match global_tcc_panel_info.show_desktop_button {
+                                        0 => {
+                                            MoveToEx(hdc_mem, 7, 2, None);
+                                            LineTo(hdc_mem, 7, rect.bottom - 2);
+                                        }
+                                        1 => {
+                                            MoveToEx(hdc_mem, (rect.right - rect.left) / 2, 0, None);
+                                            LineTo(hdc_mem, (rect.right - rect.left) / 2, rect.bottom - 2);
+                                        }
+                                        2 => {
+                                            MoveToEx(hdc_mem, rect.right - 7, 2, None);
+                                            LineTo(hdc_mem, rect.right - 7, rect.bottom - 2);
+                                        }
+                                        _ => {}
+                                    }
+                                }
+
+                                //
+                                BitBlt(hdc, 0, 0, rect.right, rect.bottom, hdc_mem, 0, 0, SRCCOPY);
+                                DeleteObject(hbm_mem);
+                                DeleteDC(hdc_mem);
+                            }
+                        }
+                    }
+                }
+
+                EndPaint(hwnd, &ps);
+
+                LRESULT(0)
+            }
+            WM_LBUTTONUP => {
+                let mut global_tcc_panel_info_hm = GLOBAL_TCC_PANEL_INFO.lock().unwrap();
+                match global_tcc_panel_info_hm.get_mut(&hwnd.0) {
+                    Some(tcc_panel_info) => {
+                        if tcc_panel_info.show_desktop_button_visible {
+                            winput::press(Vk::LeftWin);
+                            winput::send(Vk::D);
+                            winput::release(Vk::LeftWin);
+                        } else {
+                            winput::press(Vk::LeftWin);
+                            winput::send(Vk::N);
+                            winput::release(Vk::LeftWin);
+                        }
+                    }
+                    None => {
+                        winput::press(Vk::LeftWin);
+                        winput::send(Vk::N);
+                        winput::release(Vk::LeftWin);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_RBUTTONUP => {
+                let x = lparam.0 & 0xffff; //LOWORD(lparam);
+        		let y = (lparam.0 >> 16) & 0xffff; //HIWORD(lparam);
+                let mut po = POINT{
+                    x: x as i32,
+                    y: y as i32,
+                };
+                ClientToScreen(hwnd , &mut po);
+                TrackPopupMenu(
+                    *GLOBAL_MENU_HANDLE,
+                    TPM_LEFTALIGN | TPM_BOTTOMALIGN,
+                    po.x,
+                    po.y,
+                    0,
+                    hwnd,
+                    None
+                );
+                LRESULT(0)
+            }
+            WM_MOUSEMOVE => {
+                let mut window_refresh = false;
+                {
+                    let mut global_tcc_panel_info_hm = GLOBAL_TCC_PANEL_INFO.lock().unwrap();
+                    match global_tcc_panel_info_hm.get_mut(&hwnd.0) {
+                        Some(tcc_panel_info) => {
+                            if tcc_panel_info.show_desktop_button >= 0 {
+                                let mut rect: RECT = RECT::default();
+                                GetClientRect(hwnd, &mut rect);
+                                let x = lparam.0 & 0xffff;
+                                match tcc_panel_info.show_desktop_button {
+                                    0 => {
+                                        if x >= 0 && x <= 15 {
+                                            if !tcc_panel_info.show_desktop_button_visible {
+                                                tcc_panel_info.show_desktop_button_visible = true;
+                                                window_refresh = true;
+                                            }
+                                        } else {
+                                            if tcc_panel_info.show_desktop_button_visible {
+                                                tcc_panel_info.show_desktop_button_visible = false;
+                                                window_refresh = true;
+                                            }
+                                        }
+                                    }
+                                    1 => {
+                                        if x >= ((rect.right - rect.left) / 2 - 8) as isize  && x <= ((rect.right - rect.left) / 2 + 8) as isize {
+                                            if !tcc_panel_info.show_desktop_button_visible {
+                                                tcc_panel_info.show_desktop_button_visible = true;
+                                                window_refresh = true;
+                                            }
+                                        } else {
+                                            if tcc_panel_info.show_desktop_button_visible {
+                                                tcc_panel_info.show_desktop_button_visible = false;
+                                                window_refresh = true;
+                                            }
+                                        }
+                                    }
+                                    2 => {
+                                        if x >= (rect.right - rect.left - 15) as isize  && x <= (rect.right - rect.left) as isize {
+                                            if !tcc_panel_info.show_desktop_button_visible {
+                                                tcc_panel_info.show_desktop_button_visible = true;
+                                                window_refresh = true;
+                                            }
+                                        } else {
+                                            if tcc_panel_info.show_desktop_button_visible {
+                                                tcc_panel_info.show_desktop_button_visible = false;
+                                                window_refresh = true;
+                                            }
+                                        }
+                                    }
+                                    _ => {
+                                        if tcc_panel_info.show_desktop_button_visible {
+                                            tcc_panel_info.show_desktop_button_visible = false;
+                                            window_refresh = true;
+                                        }
+                                }
+                                }
+                                let mut tme = TRACKMOUSEEVENT::default();
+                                tme.cbSize = mem::size_of::<TRACKMOUSEEVENT>() as u32;
+                                tme.dwFlags = TME_LEAVE;
+                                tme.hwndTrack = hwnd;
+                                TrackMouseEvent(&mut tme);
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                if window_refresh {
+                    InvalidateRect(hwnd, None, true);
+                    UpdateWindow(hwnd);
+                }
+                LRESULT(0)
+            }
+            675 => {
+                // WM_MOUSELEAVE
+                let mut global_tcc_panel_info_hm = GLOBAL_TCC_PANEL_INFO.lock().unwrap();
+                match global_tcc_panel_info_hm.get_mut(&hwnd.0) {
+                    Some(tcc_panel_info) => {
+                        tcc_panel_info.show_desktop_button_visible = false;
+                    }
+                    None => {}
+                }
+                LRESULT(0)
+            }
+            WM_COMMAND => {
+                if wparam.0 == 1 && lparam.0 == 0 {
+                    if !GLOBAL_END {
+                        GLOBAL_END = true;
+                        PostQuitMessage(0);
+                    }
+                } else if wparam.0 == 2 && lparam.0 == 0 {
+                    if !GLOBAL_END {
+                        GLOBAL_END = true;
+                        let pid = Win32::System::Threading::GetCurrentProcessId();
+                        Win32::UI::Shell::ShellExecuteW(
+                            None,
+                            None,
+                            PCWSTR(convert_utf16_null("tcc-win11.exe").as_ptr()),
+                            PCWSTR(convert_utf16_null(&pid.to_string()).as_ptr()),
+                            PCWSTR(convert_utf16_null("").as_ptr()),
+                            SW_SHOWNORMAL
+                        );
+                        PostQuitMessage(0);
+                    }
+                }
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                if !GLOBAL_END {
+                    GLOBAL_END = true;
+                    PostQuitMessage(0);
+                }
+                LRESULT(0)
+            }
+            WM_DESTROY => {
+                if !GLOBAL_END {
+                    GLOBAL_END = true;
+                    PostQuitMessage(0);
+                }
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, message, wparam, lparam),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::f64::consts::PI;
+use chrono::*;
+
+// Jean Meeus, Astronomical Algorithms
+
+pub const J2000: f64 = 2451545.0;
+
+pub fn julian_day(dt: DateTime<Utc>) -> f64 {
+    dt.timestamp() as f64 / 86400.0 + dt.timestamp_subsec_nanos() as f64 / 86400.0e9 + 2440587.5
+}
+
+pub fn from_julian_day(jd: f64) -> DateTime<Utc> {
+    let ms = ((jd - 2440587.5) * 86400000.0).round() as i64;
+    Utc.timestamp_millis_opt(ms).unwrap()
+}
+
+// ΔT = TT - UT  (seconds)  Espenak & Meeus
+pub fn delta_t(year: f64) -> f64 {
+    if (1986.0..2005.0).contains(&year) {
+        let t = year - 2000.0;
+        63.86 + 0.3345 * t - 0.060374 * t.powi(2) + 0.0017275 * t.powi(3) + 0.000651814 * t.powi(4) + 0.00002373599 * t.powi(5)
+    } else if (2005.0..2050.0).contains(&year) {
+        let t = year - 2000.0;
+        62.92 + 0.32217 * t + 0.005589 * t.powi(2)
+    } else if (2050.0..2150.0).contains(&year) {
+        -20.0 + 32.0 * ((year - 1820.0) / 100.0).powi(2) - 0.5628 * (2150.0 - year)
+    } else if (1961.0..1986.0).contains(&year) {
+        let t = year - 1975.0;
+        45.45 + 1.067 * t - t.powi(2) / 260.0 - t.powi(3) / 718.0
+    } else if (1941.0..1961.0).contains(&year) {
+        let t = year - 1950.0;
+        29.07 + 0.407 * t - t.powi(2) / 233.0 + t.powi(3) / 2547.0
+    } else {
+        -20.0 + 32.0 * ((year - 1820.0) / 100.0).powi(2)
+    }
+}
+
+fn jd_to_year(jd: f64) -> f64 {
+    2000.0 + (jd - J2000) / 365.25
+}
+
+// UT -> TT
+pub fn to_jde(jd: f64) -> f64 {
+    jd + delta_t(jd_to_year(jd)) / 86400.0
+}
+
+// TT -> UT
+pub fn from_jde(jde: f64) -> f64 {
+    jde - delta_t(jd_to_year(jde)) / 86400.0
+}
+
+// グリニッジ平均恒星時 (degree)  jd は UT
+pub fn greenwich_mean_sidereal_time(jd: f64) -> f64 {
+    let t = (jd - J2000) / 36525.0;
+    normalize_degree(280.46061837 + 360.98564736629 * (jd - J2000) + 0.000387933 * t * t - t * t * t / 38710000.0)
+}
+
+// 地方平均恒星時 (degree)  東経がプラス
+pub fn local_mean_sidereal_time(jd: f64, longitude: f64) -> f64 {
+    normalize_degree(greenwich_mean_sidereal_time(jd) + longitude)
+}
+
+pub fn normalize_degree(x: f64) -> f64 {
+    x.rem_euclid(360.0)
+}
+
+fn sin_deg(x: f64) -> f64 {
+    x.to_radians().sin()
+}
+
+fn cos_deg(x: f64) -> f64 {
+    x.to_radians().cos()
+}
+
+// VSOP87 地球の日心黄経 (Meeus Appendix III, 主要項のみ)
+const VSOP87_L0: [(f64, f64, f64); 64] = [
+    (175347046.0, 0.0, 0.0), (3341656.0, 4.6692568, 6283.0758500), (34894.0, 4.62610, 12566.15170),
+    (3497.0, 2.7441, 5753.3849), (3418.0, 2.8289, 3.5231), (3136.0, 3.6277, 77713.7715),
+    (2676.0, 4.4181, 7860.4194), (2343.0, 6.1352, 3930.2097), (1324.0, 0.7425, 11506.7698),
+    (1273.0, 2.0371, 529.6910), (1199.0, 1.1096, 1577.3435), (990.0, 5.233, 5884.927),
+    (902.0, 2.045, 26.298), (857.0, 3.508, 398.149), (780.0, 1.179, 5223.694),
+    (753.0, 2.533, 5507.553), (505.0, 4.583, 18849.228), (492.0, 4.205, 775.523),
+    (357.0, 2.920, 0.067), (317.0, 5.849, 11790.629), (284.0, 1.899, 796.298),
+    (271.0, 0.315, 10977.079), (243.0, 0.345, 5486.778), (206.0, 4.806, 2544.314),
+    (205.0, 1.869, 5573.143), (202.0, 2.458, 6069.777), (156.0, 0.833, 213.299),
+    (132.0, 3.411, 2942.463), (126.0, 1.083, 20.775), (115.0, 0.645, 0.980),
+    (103.0, 0.636, 4694.003), (102.0, 0.976, 15720.839), (102.0, 4.267, 7.114),
+    (99.0, 6.21, 2146.17), (98.0, 0.68, 155.42), (86.0, 5.98, 161000.69),
+    (85.0, 1.30, 6275.96), (85.0, 3.67, 71430.70), (80.0, 1.81, 17260.15),
+    (79.0, 3.04, 12036.46), (75.0, 1.76, 5088.63), (74.0, 3.50, 3154.69),
+    (74.0, 4.68, 801.82), (70.0, 0.83, 9437.76), (62.0, 3.98, 8827.39),
+    (61.0, 1.82, 7084.90), (57.0, 2.78, 6286.60), (56.0, 4.39, 14143.50),
+    (56.0, 3.47, 6279.55), (52.0, 0.19, 12139.55), (52.0, 1.33, 1748.02),
+    (51.0, 0.28, 5856.48), (49.0, 0.49, 1194.45), (41.0, 5.37, 8429.24),
+    (41.0, 2.40, 19651.05), (39.0, 6.17, 10447.39), (37.0, 6.04, 10213.29),
+    (37.0, 2.57, 1059.38), (36.0, 1.71, 2352.87), (36.0, 1.78, 6812.77),
+    (33.0, 0.59, 17789.85), (30.0, 0.44, 83996.85), (30.0, 2.74, 1349.87),
+    (25.0, 3.16, 4690.48),
+];
+const VSOP87_L1: [(f64, f64, f64); 34] = [
+    (628331966747.0, 0.0, 0.0), (206059.0, 2.678235, 6283.075850), (4303.0, 2.6351, 12566.1517),
+    (425.0, 1.590, 3.523), (119.0, 5.796, 26.298), (109.0, 2.966, 1577.344),
+    (93.0, 2.59, 18849.23), (72.0, 1.14, 529.69), (68.0, 1.87, 398.15),
+    (67.0, 4.41, 5507.55), (59.0, 2.89, 5223.69), (56.0, 2.17, 155.42),
+    (45.0, 0.40, 796.30), (36.0, 0.47, 775.52), (29.0, 2.65, 7.11),
+    (21.0, 5.34, 0.98), (19.0, 1.85, 5486.78), (19.0, 4.97, 213.30),
+    (17.0, 2.99, 6275.96), (16.0, 0.03, 2544.31), (16.0, 1.43, 2146.17),
+    (15.0, 1.21, 10977.08), (12.0, 2.83, 1748.02), (12.0, 3.26, 5088.63),
+    (12.0, 5.27, 1194.45), (12.0, 2.08, 4694.00), (11.0, 0.77, 553.57),
+    (10.0, 1.30, 6286.60), (10.0, 4.24, 1349.87), (9.0, 2.70, 242.73),
+    (9.0, 5.64, 951.72), (8.0, 5.30, 2352.87), (6.0, 2.65, 9437.76),
+    (6.0, 4.67, 4690.48),
+];
+const VSOP87_L2: [(f64, f64, f64); 20] = [
+    (52919.0, 0.0, 0.0), (8720.0, 1.0721, 6283.0758), (309.0, 0.867, 12566.152),
+    (27.0, 0.05, 3.52), (16.0, 5.19, 26.30), (16.0, 3.68, 155.42),
+    (10.0, 0.76, 18849.23), (9.0, 2.06, 77713.77), (7.0, 0.83, 775.52),
+    (5.0, 4.66, 1577.34), (4.0, 1.03, 7.11), (4.0, 3.44, 5573.14),
+    (3.0, 5.14, 796.30), (3.0, 6.05, 5507.55), (3.0, 1.19, 242.73),
+    (3.0, 6.12, 529.69), (3.0, 0.31, 398.15), (3.0, 2.28, 553.57),
+    (2.0, 4.38, 5223.69), (2.0, 3.75, 0.98),
+];
+const VSOP87_L3: [(f64, f64, f64); 7] = [
+    (289.0, 5.844, 6283.076), (35.0, 0.0, 0.0), (17.0, 5.49, 12566.15),
+    (3.0, 5.20, 155.42), (1.0, 4.72, 3.52), (1.0, 5.30, 18849.23),
+    (1.0, 5.97, 242.73),
+];
+const VSOP87_L4: [(f64, f64, f64); 3] = [
+    (114.0, PI, 0.0), (8.0, 4.13, 6283.08), (1.0, 3.84, 12566.15),
+];
+
+fn vsop87_sum(terms: &[(f64, f64, f64)], tau: f64) -> f64 {
+    terms.iter().map(|(a, b, c)| a * (b + c * tau).cos()).sum()
+}
+
+// 章動 Δψ (degree)
+pub fn nutation_longitude(jde: f64) -> f64 {
+    let t = (jde - J2000) / 36525.0;
+    let omega = 125.04452 - 1934.136261 * t;
+    let l = 280.4665 + 36000.7698 * t;
+    let lp = 218.3165 + 481267.8813 * t;
+    (-17.20 * sin_deg(omega) - 1.32 * sin_deg(2.0 * l) - 0.23 * sin_deg(2.0 * lp) + 0.21 * sin_deg(2.0 * omega)) / 3600.0
+}
+
+// 太陽の視黄経 (degree)
+pub fn sun_longitude(jde: f64) -> f64 {
+    let tau = (jde - J2000) / 365250.0;
+    let l = vsop87_sum(&VSOP87_L0, tau)
+        + vsop87_sum(&VSOP87_L1, tau) * tau
+        + vsop87_sum(&VSOP87_L2, tau) * tau.powi(2)
+        + vsop87_sum(&VSOP87_L3, tau) * tau.powi(3)
+        + vsop87_sum(&VSOP87_L4, tau) * tau.powi(4)
+        - tau.powi(5);
+    let geometric = (l / 1.0e8).to_degrees() + 180.0;
+    // FK5, 光行差
+    normalize_degree(geometric - 0.09033 / 3600.0 + nutation_longitude(jde) - 20.4898 / 3600.0)
+}
+
+// 太陽の視赤緯 (degree), 均時差 (minute)
+pub fn sun_declination_equation_of_time(jde: f64) -> (f64, f64) {
+    let t = (jde - J2000) / 36525.0;
+    let l0 = 280.46646 + 36000.76983 * t + 0.0003032 * t * t;
+    let e = 0.016708634 - 0.000042037 * t - 0.0000001267 * t * t;
+    let m = 357.52911 + 35999.05029 * t - 0.0001537 * t * t;
+    let omega = 125.04 - 1934.136 * t;
+    let epsilon = 23.0 + (26.0 + (21.448 - 46.8150 * t - 0.00059 * t * t + 0.001813 * t * t * t) / 60.0) / 60.0 + 0.00256 * cos_deg(omega);
+    let declination = (sin_deg(epsilon) * sin_deg(sun_longitude(jde))).asin().to_degrees();
+    let y = (epsilon / 2.0).to_radians().tan().powi(2);
+    let eot = y * sin_deg(2.0 * l0) - 2.0 * e * sin_deg(m) + 4.0 * e * y * sin_deg(m) * cos_deg(2.0 * l0)
+        - 0.5 * y * y * sin_deg(4.0 * l0) - 1.25 * e * e * sin_deg(2.0 * m);
+    (declination, eot.to_degrees() * 4.0)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TccSunRiseSet {
+    Normal(DateTime<Utc>, DateTime<Utc>),
+    PolarDay,
+    PolarNight,
+}
+
+// 日の出・日の入り  (大気差と太陽の視半径 -0.833°)
+// date の正午 (offset のタイムゾーン) に近い南中の前後を計算する
+pub fn sun_rise_set(date: NaiveDate, offset: i32, latitude: f64, longitude: f64) -> TccSunRiseSet {
+    let local_noon = date.and_hms_opt(12, 0, 0).unwrap() - Duration::seconds(offset as i64);
+    let local_noon_jd = julian_day(Utc.from_utc_datetime(&local_noon));
+    // 南中 (JD UT)
+    let mut transit = local_noon_jd;
+    for _ in 0..3 {
+        let (_, eot) = sun_declination_equation_of_time(to_jde(transit));
+        let utc_midnight = (transit - 0.5).floor() + 0.5;
+        transit = utc_midnight + 0.5 - longitude / 360.0 - eot / 1440.0;
+        if transit - local_noon_jd > 0.5 {
+            transit -= 1.0;
+        } else if local_noon_jd - transit > 0.5 {
+            transit += 1.0;
+        }
+    }
+    // 時角
+    let hour_angle = |jd: f64| -> Option<f64> {
+        let (declination, _) = sun_declination_equation_of_time(to_jde(jd));
+        let x = (sin_deg(-0.833) - sin_deg(latitude) * sin_deg(declination)) / (cos_deg(latitude) * cos_deg(declination));
+        if x > 1.0 {
+            None
+        } else if x < -1.0 {
+            Some(180.0)
+        } else {
+            Some(x.acos().to_degrees())
+        }
+    };
+    let mut rise = transit;
+    let mut set = transit;
+    for _ in 0..3 {
+        match (hour_angle(rise), hour_angle(set)) {
+            (Some(x), Some(y)) if x < 180.0 && y < 180.0 => {
+                rise = transit - x / 360.0;
+                set = transit + y / 360.0;
+            }
+            (None, _) | (_, None) => return TccSunRiseSet::PolarNight,
+            _ => return TccSunRiseSet::PolarDay,
+        }
+    }
+    TccSunRiseSet::Normal(from_julian_day(rise), from_julian_day(set))
+}
+
+// 月齢 (日)  直近の朔からの経過日数
+pub fn moon_age(jd: f64) -> f64 {
+    jd - new_moon(new_moon_k_before(jd))
+}
+
+// 月相  月齢を8つに分ける
+pub fn moon_phase_name(moon_age: f64) -> &'static str {
+    const NAMES: [&str; 8] = ["新月", "三日月", "上弦", "十三夜", "満月", "寝待月", "下弦", "有明月"];
+    let i = ((moon_age / 29.530588861 * 8.0 + 0.5).floor() as usize) % 8;
+    NAMES[i]
+}
+
+// 太陽黄経が longitude になる時刻 (JD UT)  jd の近くを探す
+pub fn solve_sun_longitude(longitude: f64, jd: f64) -> f64 {
+    let mut jde = to_jde(jd);
+    for _ in 0..20 {
+        let diff = (longitude - sun_longitude(jde) + 540.0).rem_euclid(360.0) - 180.0;
+        jde += diff * 365.2422 / 360.0;
+        if diff.abs() < 0.000001 {
+            break;
+        }
+    }
+    from_jde(jde)
+}
+
+// 朔 (JD UT)  k = 0 : 2000/01/06
+pub fn new_moon(k: f64) -> f64 {
+    let t = k / 1236.85;
+    let jde = 2451550.09766 + 29.530588861 * k + 0.00015437 * t.powi(2) - 0.000000150 * t.powi(3) + 0.00000000073 * t.powi(4);
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t.powi(2);
+    let m = 2.5534 + 29.10535670 * k - 0.0000014 * t.powi(2) - 0.00000011 * t.powi(3);
+    let mp = 201.5643 + 385.81693528 * k + 0.0107582 * t.powi(2) + 0.00001238 * t.powi(3) - 0.000000058 * t.powi(4);
+    let f = 160.7108 + 390.67050284 * k - 0.0016118 * t.powi(2) - 0.00000227 * t.powi(3) + 0.000000011 * t.powi(4);
+    let omega = 124.7746 - 1.56375588 * k + 0.0020672 * t.powi(2) + 0.00000215 * t.powi(3);
+    let correction = -0.40720 * sin_deg(mp)
+        + 0.17241 * e * sin_deg(m)
+        + 0.01608 * sin_deg(2.0 * mp)
+        + 0.01039 * sin_deg(2.0 * f)
+        + 0.00739 * e * sin_deg(mp - m)
+        - 0.00514 * e * sin_deg(mp + m)
+        + 0.00208 * e * e * sin_deg(2.0 * m)
+        - 0.00111 * sin_deg(mp - 2.0 * f)
+        - 0.00057 * sin_deg(mp + 2.0 * f)
+        + 0.00056 * e * sin_deg(2.0 * mp + m)
+        - 0.00042 * sin_deg(3.0 * mp)
+        + 0.00042 * e * sin_deg(m + 2.0 * f)
+        + 0.00038 * e * sin_deg(m - 2.0 * f)
+        - 0.00024 * e * sin_deg(2.0 * mp - m)
+        - 0.00017 * sin_deg(omega)
+        - 0.00007 * sin_deg(mp + 2.0 * m)
+        + 0.00004 * sin_deg(2.0 * mp - 2.0 * f)
+        + 0.00004 * sin_deg(3.0 * m)
+        + 0.00003 * sin_deg(mp + m - 2.0 * f)
+        + 0.00003 * sin_deg(2.0 * mp + 2.0 * f)
+        - 0.00003 * sin_deg(mp + m + 2.0 * f)
+        + 0.00003 * sin_deg(mp - m + 2.0 * f)
+        - 0.00002 * sin_deg(mp - m - 2.0 * f)
+        - 0.00002 * sin_deg(3.0 * mp + m)
+        + 0.00002 * sin_deg(4.0 * mp);
+    let a = [
+        (0.000325, 299.77 + 0.107408 * k - 0.009173 * t * t),
+        (0.000165, 251.88 + 0.016321 * k),
+        (0.000164, 251.83 + 26.651886 * k),
+        (0.000126, 349.42 + 36.412478 * k),
+        (0.000110, 84.66 + 18.206239 * k),
+        (0.000062, 141.74 + 53.303771 * k),
+        (0.000060, 207.14 + 2.453732 * k),
+        (0.000056, 154.84 + 7.306860 * k),
+        (0.000047, 34.52 + 27.261239 * k),
+        (0.000042, 207.19 + 0.121824 * k),
+        (0.000040, 291.34 + 1.844379 * k),
+        (0.000037, 161.72 + 24.198154 * k),
+        (0.000035, 239.56 + 25.513099 * k),
+        (0.000023, 331.55 + 3.592518 * k),
+    ];
+    let additional: f64 = a.iter().map(|(x, y)| x * sin_deg(*y)).sum();
+    from_jde(jde + correction + additional)
+}
+
+// jd 以前の直近の朔の k
+pub fn new_moon_k_before(jd: f64) -> f64 {
+    let mut k = ((jd - 2451550.09766) / 29.530588861).floor();
+    while new_moon(k) > jd {
+        k -= 1.0;
+    }
+    while new_moon(k + 1.0) <= jd {
+        k += 1.0;
+    }
+    k
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JST: i32 = 9 * 3600;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    // 1分以内
+    fn assert_near(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        assert!((actual - expected).num_seconds().abs() <= 60, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn julian_day_at_j2000() {
+        assert_eq!(julian_day(utc(2000, 1, 1, 12, 0)), J2000);
+        assert_eq!(julian_day(utc(1970, 1, 1, 0, 0)), 2440587.5);
+        assert_eq!(from_julian_day(J2000), utc(2000, 1, 1, 12, 0));
+    }
+
+    // Meeus 例題 12.a, 12.b
+    #[test]
+    fn sidereal_time_meeus_examples() {
+        let jd = julian_day(utc(1987, 4, 10, 0, 0));
+        assert_eq!(jd, 2446895.5);
+        // 13h10m46.3668s
+        assert!((greenwich_mean_sidereal_time(jd) * 240.0 - 47446.3668).abs() < 0.001);
+        // 19:21:00 UT => 8h34m57.0896s
+        let jd = julian_day(utc(1987, 4, 10, 19, 21));
+        assert!((greenwich_mean_sidereal_time(jd) * 240.0 - 30897.0896).abs() < 0.001);
+        // 東経 135度 は +9h, 西経 77.0656度 (ワシントン) は -5h08m15.744s
+        let lmst = local_mean_sidereal_time(julian_day(utc(1987, 4, 10, 0, 0)), 135.0);
+        assert!((lmst * 240.0 - (47446.3668 + 9.0 * 3600.0)).abs() < 0.001);
+        assert_eq!(crate::duration::format_duration((lmst * 240.0).floor() as i64, "%H:%M:%S"), "22:10:46");
+        let lmst = local_mean_sidereal_time(julian_day(utc(1987, 4, 10, 0, 0)), -77.0656);
+        assert!((lmst * 240.0 - (47446.3668 - 18495.744)).abs() < 0.001);
+    }
+
+    #[test]
+    fn sun_rise_set_tokyo() {
+        // 東京 2024/06/21  日の出 04:25  日の入り 19:00 (JST)
+        let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let TccSunRiseSet::Normal(rise, set) = sun_rise_set(date, JST, 35.6895, 139.6917) else {
+            panic!("no sunrise");
+        };
+        assert_near(rise, utc(2024, 6, 20, 19, 25));
+        assert_near(set, utc(2024, 6, 21, 10, 0));
+    }
+
+    #[test]
+    fn sun_rise_set_svalbard() {
+        // ロングイェールビーン  白夜・極夜
+        let (latitude, longitude, offset) = (78.22, 15.65, 3600);
+        let summer = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let winter = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+        assert_eq!(sun_rise_set(summer, offset, latitude, longitude), TccSunRiseSet::PolarDay);
+        assert_eq!(sun_rise_set(winter, offset, latitude, longitude), TccSunRiseSet::PolarNight);
+    }
+
+    #[test]
+    fn new_moon_and_moon_age() {
+        // 2024/01/11 11:57 UTC 新月
+        let jd = julian_day(utc(2024, 1, 11, 11, 57));
+        let k = new_moon_k_before(jd + 1.0);
+        assert_near(from_julian_day(new_moon(k)), utc(2024, 1, 11, 11, 57));
+        let age = moon_age(jd + 7.0);
+        assert!((age - 7.0).abs() < 0.01, "{age}");
+        assert_eq!(moon_phase_name(age), "上弦");
+        assert_eq!(moon_phase_name(moon_age(jd + 0.5)), "新月");
+        assert_eq!(moon_phase_name(moon_age(jd + 14.8)), "満月");
+    }
+
+    #[test]
+    fn sun_longitude_at_equinox() {
+        // 2024/03/20 03:06 UTC 春分
+        let jd = solve_sun_longitude(0.0, julian_day(utc(2024, 3, 19, 0, 0)));
+        assert_near(from_julian_day(jd), utc(2024, 3, 20, 3, 6));
+    }
+}
@@ -0,0 +1,226 @@
+use chrono::*;
+use anyhow::*;
+use crate::holiday;
+
+// 営業時間  label > business_hours
+//   "09:00-18:00 Mon-Fri"
+//   "09:00-12:00,13:00-18:00 Mon-Fri; 10:00-15:00 Sat"
+//   "22:00-06:00 Mon-Fri"   => 日付をまたぐ (開始の曜日で判断)
+//   "00:00-24:00"           => 曜日を省略すると毎日
+#[derive(Debug, Default, Clone)]
+pub struct TccBusinessHours {
+    rules: Vec<TccBusinessHoursRule>,
+    // 休業日  national: 日本の祝日 + holiday_files
+    pub national_holidays: bool,
+    pub holidays: holiday::TccHolidayCalendar,
+}
+
+#[derive(Debug, Default, Clone)]
+struct TccBusinessHoursRule {
+    weekdays: Vec<Weekday>,
+    // (開始, 開始からの長さ)
+    ranges: Vec<(NaiveTime, Duration)>,
+}
+
+// 営業時間を探す期間
+const MAX_SEARCH_DAYS: i64 = 14;
+
+fn parse_time(s: &str) -> Option<(NaiveTime, bool)> {
+    if s.trim() == "24:00" {
+        return Some((NaiveTime::MIN, true));
+    }
+    NaiveTime::parse_from_str(s.trim(), "%H:%M").ok().map(|x| (x, false))
+}
+
+fn parse_weekdays(s: &str) -> anyhow::Result<Vec<Weekday>> {
+    let mut weekdays: Vec<Weekday> = Vec::new();
+    for item in s.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        let (from, to) = item.split_once('-').unwrap_or((item, item));
+        let (std::result::Result::Ok(from), std::result::Result::Ok(to)) = (from.trim().parse::<Weekday>(), to.trim().parse::<Weekday>()) else {
+            return Err(anyhow!(format!("incorrect weekday : {item}")));
+        };
+        // "Sat-Mon" => Sat, Sun, Mon
+        let mut weekday = from;
+        loop {
+            if !weekdays.contains(&weekday) {
+                weekdays.push(weekday);
+            }
+            if weekday == to {
+                break;
+            }
+            weekday = weekday.succ();
+        }
+    }
+    Ok(weekdays)
+}
+
+impl TccBusinessHours {
+    pub fn parse(s: &str) -> anyhow::Result<TccBusinessHours> {
+        let mut business_hours = TccBusinessHours::default();
+        for group in s.split(';').map(|x| x.trim()).filter(|x| !x.is_empty()) {
+            let (ranges, weekdays) = group.split_once(char::is_whitespace).unwrap_or((group, ""));
+            let mut rule = TccBusinessHoursRule {
+                weekdays: parse_weekdays(weekdays)?,
+                ..Default::default()
+            };
+            if rule.weekdays.is_empty() {
+                rule.weekdays = parse_weekdays("Mon-Sun")?;
+            }
+            for range in ranges.split(',') {
+                let times = range.split_once('-').and_then(|(from, to)| parse_time(from).zip(parse_time(to)));
+                match times {
+                    Some(((from, false), (to, is_24))) => {
+                        let mut length = to - from;
+                        if is_24 || length <= Duration::zero() {
+                            length += Duration::days(1);
+                        }
+                        rule.ranges.push((from, length));
+                    }
+                    _ => {
+                        return Err(anyhow!(format!("incorrect time : {range}  (09:00-18:00)")));
+                    }
+                }
+            }
+            business_hours.rules.push(rule);
+        }
+        if business_hours.rules.is_empty() {
+            return Err(anyhow!("nothing : 09:00-18:00 Mon-Fri"));
+        }
+        Ok(business_hours)
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        if self.national_holidays {
+            self.holidays.get_holiday_name(date).is_some()
+        } else {
+            self.holidays.get(date).is_some()
+        }
+    }
+
+    // 営業時間 (開始, 終了)  from の前日から MAX_SEARCH_DAYS 日分、重なりはまとめる
+    fn get_intervals<T: TimeZone>(&self, from: &DateTime<T>) -> Vec<(DateTime<T>, DateTime<T>)> {
+        let tz = from.timezone();
+        let mut intervals: Vec<(DateTime<T>, DateTime<T>)> = Vec::new();
+        let start_date = from.date_naive() - Duration::days(1);
+        for date in start_date.iter_days().take(MAX_SEARCH_DAYS as usize + 1) {
+            if self.is_holiday(date) {
+                continue;
+            }
+            for rule in self.rules.iter().filter(|x| x.weekdays.contains(&date.weekday())) {
+                for (time, length) in rule.ranges.iter() {
+                    let start = date.and_time(*time);
+                    // 夏時間で存在しない時刻は1時間後にずらす
+                    let start_local = tz.from_local_datetime(&start).earliest().or(tz.from_local_datetime(&(start + Duration::hours(1))).earliest());
+                    let end_local = tz.from_local_datetime(&(start + *length)).earliest().or(tz.from_local_datetime(&(start + *length + Duration::hours(1))).earliest());
+                    if let (Some(x), Some(y)) = (start_local, end_local) {
+                        intervals.push((x, y));
+                    }
+                }
+            }
+        }
+        intervals.sort_by_key(|x| x.0.clone());
+        let mut merged: Vec<(DateTime<T>, DateTime<T>)> = Vec::new();
+        for (start, end) in intervals {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => {
+                    if end > last.1 {
+                        last.1 = end;
+                    }
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+        merged
+    }
+
+    pub fn is_open<T: TimeZone>(&self, now: &DateTime<T>) -> bool {
+        self.get_intervals(now).iter().any(|(start, end)| start <= now && now < end)
+    }
+
+    // 営業中 => 閉まる時刻, 営業時間外 => 開く時刻
+    pub fn get_next_change<T: TimeZone>(&self, now: &DateTime<T>) -> Option<DateTime<T>> {
+        self.get_intervals(now).into_iter().find_map(|(start, end)| {
+            if now < &start {
+                Some(start)
+            } else if now < &end {
+                Some(end)
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jst(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<FixedOffset> {
+        FixedOffset::east_opt(9 * 3600).unwrap().with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn parse_business_hours() {
+        let x = TccBusinessHours::parse("09:00-12:00,13:00-18:00 Mon-Fri; 10:00-15:00 Sat").unwrap();
+        assert_eq!(x.rules.len(), 2);
+        assert_eq!(x.rules[0].weekdays, vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]);
+        assert_eq!(x.rules[0].ranges[1], (NaiveTime::from_hms_opt(13, 0, 0).unwrap(), Duration::hours(5)));
+        assert_eq!(x.rules[1].weekdays, vec![Weekday::Sat]);
+
+        let x = TccBusinessHours::parse("22:00-06:00 Sat-Mon").unwrap();
+        assert_eq!(x.rules[0].weekdays, vec![Weekday::Sat, Weekday::Sun, Weekday::Mon]);
+        assert_eq!(x.rules[0].ranges[0].1, Duration::hours(8));
+
+        let x = TccBusinessHours::parse("00:00-24:00").unwrap();
+        assert_eq!(x.rules[0].weekdays.len(), 7);
+        assert_eq!(x.rules[0].ranges[0].1, Duration::days(1));
+
+        assert!(TccBusinessHours::parse("").is_err());
+        assert!(TccBusinessHours::parse("9-18 Mon-Fri").is_err());
+        assert!(TccBusinessHours::parse("09:00-18:00 Mon-Fry").is_err());
+        assert!(TccBusinessHours::parse("24:00-06:00").is_err());
+    }
+
+    #[test]
+    fn next_change_across_weekend() {
+        let x = TccBusinessHours::parse("09:00-18:00 Mon-Fri").unwrap();
+        // 2024-03-08 は金曜日
+        assert!(x.is_open(&jst(2024, 3, 8, 17, 59)));
+        assert_eq!(x.get_next_change(&jst(2024, 3, 8, 17, 59)), Some(jst(2024, 3, 8, 18, 0)));
+        assert!(!x.is_open(&jst(2024, 3, 8, 18, 0)));
+        assert_eq!(x.get_next_change(&jst(2024, 3, 8, 18, 0)), Some(jst(2024, 3, 11, 9, 0)));
+        assert_eq!(x.get_next_change(&jst(2024, 3, 10, 12, 0)), Some(jst(2024, 3, 11, 9, 0)));
+        assert!(x.is_open(&jst(2024, 3, 11, 9, 0)));
+    }
+
+    #[test]
+    fn overnight_hours_belong_to_the_start_day() {
+        let x = TccBusinessHours::parse("22:00-06:00 Mon-Fri").unwrap();
+        // 金曜日の 22:00 から土曜日の 06:00 まで
+        assert!(x.is_open(&jst(2024, 3, 9, 3, 0)));
+        assert_eq!(x.get_next_change(&jst(2024, 3, 9, 3, 0)), Some(jst(2024, 3, 9, 6, 0)));
+        assert_eq!(x.get_next_change(&jst(2024, 3, 9, 6, 0)), Some(jst(2024, 3, 11, 22, 0)));
+        // 月曜日の 03:00 は日曜日の夜なので営業時間外
+        assert!(!x.is_open(&jst(2024, 3, 11, 3, 0)));
+    }
+
+    #[test]
+    fn national_holidays_are_closed() {
+        let mut x = TccBusinessHours::parse("09:00-18:00 Mon-Fri").unwrap();
+        x.national_holidays = true;
+        // 2024-03-20 (水) は春分の日
+        assert!(!x.is_open(&jst(2024, 3, 20, 12, 0)));
+        assert_eq!(x.get_next_change(&jst(2024, 3, 19, 18, 0)), Some(jst(2024, 3, 21, 9, 0)));
+        x.national_holidays = false;
+        assert!(x.is_open(&jst(2024, 3, 20, 12, 0)));
+    }
+
+    #[test]
+    fn nonexistent_start_time_is_shifted() {
+        // ベルリン 2024-03-31 02:00 => 03:00
+        let x = TccBusinessHours::parse("02:30-05:00 Sun").unwrap();
+        let now = chrono_tz::Europe::Berlin.with_ymd_and_hms(2024, 3, 31, 0, 0, 0).unwrap();
+        let open = chrono_tz::Europe::Berlin.with_ymd_and_hms(2024, 3, 31, 3, 30, 0).unwrap();
+        assert_eq!(x.get_next_change(&now), Some(open));
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::*;
+use crate::filter;
+
+// "holiday"           => {holiday} is not "" / "0" / "false"
+// "!holiday"          => not
+// "%w == 0"           => compare  (==, !=, <, <=, >, >=)  numbers are compared as numbers
+//                         a value that is not a number ("--") is only != to a number
+// "-cpu >= 80 && ac == 0"
+#[derive(Debug, Default, Clone)]
+pub struct TccCondition {
+    terms: Vec<TccConditionTerm>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct TccConditionTerm {
+    not: bool,
+    placeholder: filter::TccPlaceholder,
+    operator: String,
+    value: String,
+}
+
+const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+impl TccCondition {
+    pub fn parse(s: &str, takes_format: fn(&str) -> bool) -> anyhow::Result<TccCondition> {
+        let mut condition = TccCondition::default();
+        for term in s.split("&&") {
+            let mut term = term.trim();
+            let mut condition_term = TccConditionTerm::default();
+            if let Some(x) = term.strip_prefix('!') {
+                if !x.starts_with('=') {
+                    condition_term.not = true;
+                    term = x.trim_start();
+                }
+            }
+            let mut left = term;
+            // "== 1" => left is empty
+            let padded = format!(" {term} ");
+            for operator in OPERATORS {
+                if let Some((l, r)) = padded.split_once(&format!(" {operator} ")) {
+                    left = l.trim();
+                    condition_term.operator = operator.to_string();
+                    condition_term.value = r.trim().trim_matches('\'').to_string();
+                    break;
+                }
+            }
+            let left = left.trim_start_matches('{').trim_end_matches('}');
+            if left.is_empty() {
+                return Err(anyhow!(format!("incorrect condition : {s}")));
+            }
+            condition_term.placeholder = filter::parse_placeholder(left, takes_format)?;
+            condition.terms.push(condition_term);
+        }
+        Ok(condition)
+    }
+
+    // placeholder names used by condition
+    pub fn get_placeholder_names(&self) -> Vec<String> {
+        self.terms.iter().map(|x| x.placeholder.name.to_string()).collect()
+    }
+
+    pub fn eval<F: Fn(&filter::TccPlaceholder) -> String>(&self, get_value: F) -> bool {
+        self.terms.iter().all(|term| {
+            let value = get_value(&term.placeholder);
+            let value = value.trim();
+            let result = match term.operator.as_str() {
+                "" => !(value.is_empty() || value == "0" || value == "false"),
+                operator => {
+                    let ordering = match (value.parse::<f64>(), term.value.parse::<f64>()) {
+                        (std::result::Result::Ok(a), std::result::Result::Ok(b)) => a.partial_cmp(&b),
+                        (Err(_), std::result::Result::Ok(_)) => None,
+                        _ => Some(value.cmp(&term.value)),
+                    };
+                    match ordering {
+                        None => operator == "!=",
+                        Some(ordering) => match operator {
+                            "==" => ordering.is_eq(),
+                            "!=" => ordering.is_ne(),
+                            "<" => ordering.is_lt(),
+                            "<=" => ordering.is_le(),
+                            ">" => ordering.is_gt(),
+                            ">=" => ordering.is_ge(),
+                            _ => false,
+                        },
+                    }
+                }
+            };
+            result != term.not
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(s: &str, values: &[(&str, &str)]) -> bool {
+        let condition = TccCondition::parse(s, |_| false).unwrap();
+        condition.eval(|placeholder| values.iter().find(|x| x.0 == placeholder.name).map(|x| x.1).unwrap_or("").to_string())
+    }
+
+    #[test]
+    fn bare_placeholder_is_truthy() {
+        for (value, expected) in [("", false), ("0", false), ("false", false), (" 0 ", false), ("1", true), ("元日", true)] {
+            assert_eq!(eval("holiday", &[("holiday", value)]), expected, "{value:?}");
+            assert_eq!(eval("{holiday}", &[("holiday", value)]), expected, "{value:?}");
+            assert_eq!(eval("!holiday", &[("holiday", value)]), !expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn not_equal_is_not_negation() {
+        assert!(eval("ac != 1", &[("ac", "0")]));
+        assert!(!eval("ac != 1", &[("ac", "1")]));
+        assert!(eval("!ac == 1", &[("ac", "0")]));
+        assert!(TccCondition::parse("!= 1", |_| false).is_err());
+    }
+
+    #[test]
+    fn operators_compare_numbers_then_strings() {
+        let values = [("cpu", " 10"), ("%a", "Sat")];
+        for (s, expected) in [
+            ("cpu == 10", true), ("cpu == 10.0", true), ("cpu != 10", false),
+            ("cpu < 9", false), ("cpu <= 10", true), ("cpu > 9", true), ("cpu >= 11", false),
+            ("%a == Sat", true), ("%a == 'Sat'", true), ("%a != Sun", true),
+            ("%a < Sun", true), ("%a > Sun", false), ("%a <= Sat", true), ("%a >= Sun", false),
+        ] {
+            assert_eq!(eval(s, &values), expected, "{s}");
+        }
+        // 数値どうしは数値で比べる (文字列なら "10" < "9")
+        assert!(!eval("cpu < 9", &[("cpu", "10")]));
+        assert!(eval("name < abd", &[("name", "abc")]));
+    }
+
+    #[test]
+    fn all_terms_must_match() {
+        let values = [("cpu", "85"), ("ac", "0")];
+        assert!(eval("cpu >= 80 && ac == 0", &values));
+        assert!(!eval("cpu >= 80 && ac == 1", &values));
+        assert!(!eval("cpu >= 90 && ac == 0", &values));
+        assert!(eval("cpu >= 80 && !holiday", &values));
+    }
+
+    #[test]
+    fn missing_value_does_not_match_numbers() {
+        let values = [("ping:example.com", "--")];
+        for operator in ["==", "<", "<=", ">", ">="] {
+            assert!(!eval(&format!("ping:example.com {operator} 100"), &values), "{operator}");
+        }
+        assert!(eval("ping:example.com != 100", &values));
+        assert!(eval("ping:example.com == --", &values));
+    }
+
+    #[test]
+    fn empty_left_side_is_error() {
+        for s in ["", " ", "{}", " == 1", "cpu > 1 && ", "!"] {
+            assert!(TccCondition::parse(s, |_| false).is_err(), "{s:?}");
+        }
+        assert_eq!(TccCondition::parse("cpu > 1 && {holiday}", |_| false).unwrap().get_placeholder_names(), vec!["cpu", "holiday"]);
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use anyhow::*;
+use crate::filter;
+
+// config.txt > custom_formats
+//   value  : strftime  (source がなければ)
+//   source : placeholder  {cpu}, {w_jp} など
+//   items  : replace  (config.txt の順。範囲が重なったら先に書いた方)
+#[derive(Debug, Default, Clone)]
+pub struct TccCustomFormat {
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub spec: String,
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub value: String,
+    pub source: Option<filter::TccPlaceholder>,
+    pub items: Vec<(String, String)>,
+}
+
+impl TccCustomFormat {
+    fn get_item(&self, key: &str) -> Option<&str> {
+        self.items.iter().find(|x| x.0 == key).map(|x| x.1.as_str())
+    }
+
+    pub fn replace(&self, replace_source: &str) -> String {
+        if let Some(x) = self.get_item(replace_source).or(self.get_item(replace_source.trim())) {
+            return x.to_string();
+        }
+        // range  "0..30" => 0 <= x < 30, "80.." => 80 <= x, "..20" => x < 20
+        if let std::result::Result::Ok(number) = replace_source.trim().parse::<f64>() {
+            for (k, v) in self.items.iter() {
+                if let Some((from, to)) = k.split_once("..") {
+                    let from = from.trim().parse::<f64>().ok();
+                    let to = to.trim().parse::<f64>().ok();
+                    if from.is_none() && to.is_none() {
+                        continue;
+                    }
+                    if from.is_none_or(|x| x <= number) && to.is_none_or(|x| number < x) {
+                        return v.to_string();
+                    }
+                }
+            }
+        }
+        self.get_item("_").unwrap_or("").to_string()
+    }
+}
+
+// custom_formats > source must not refer to itself  a -> b -> a
+pub fn check_custom_format_source(custom_format_hm: &HashMap<String, TccCustomFormat>) -> anyhow::Result<()> {
+    let mut specs: Vec<&String> = custom_format_hm.keys().collect();
+    specs.sort();
+    for spec in specs {
+        let mut path = vec![spec.as_str()];
+        let mut current = &custom_format_hm[spec];
+        while let Some(source) = &current.source {
+            if path.contains(&source.name.as_str()) {
+                path.push(&source.name);
+                return Err(anyhow!(format!("[circular reference] config.txt custom_formats > {spec} > source => {}", path.join(" -> "))));
+            }
+            match custom_format_hm.get(&source.name) {
+                None => break,
+                Some(x) => {
+                    path.push(&source.name);
+                    current = x;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom_format(items: &[(&str, &str)]) -> TccCustomFormat {
+        TccCustomFormat {
+            items: items.iter().map(|x| (x.0.to_string(), x.1.to_string())).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn source(spec: &str, source: Option<&str>) -> (String, TccCustomFormat) {
+        let tcc_custom_format = TccCustomFormat {
+            spec: spec.to_string(),
+            source: source.map(|x| filter::parse_placeholder(x, |_| false).unwrap()),
+            ..Default::default()
+        };
+        (spec.to_string(), tcc_custom_format)
+    }
+
+    #[test]
+    fn replace_prefers_exact_items_then_ranges_then_other() {
+        let tcc_custom_format = custom_format(&[("0..30", "😀"), ("50", "half"), ("30..80", "😐"), ("80..", "🥵"), ("_", "?")]);
+        assert_eq!(tcc_custom_format.replace("  5"), "😀");
+        assert_eq!(tcc_custom_format.replace("50"), "half");
+        assert_eq!(tcc_custom_format.replace("30"), "😐");
+        assert_eq!(tcc_custom_format.replace("100"), "🥵");
+        assert_eq!(tcc_custom_format.replace("--"), "?");
+        assert_eq!(custom_format(&[("1", "a")]).replace("2"), "");
+    }
+
+    #[test]
+    fn replace_uses_the_first_overlapping_range() {
+        let first = custom_format(&[("0..50", "low"), ("..100", "any"), ("40..60", "mid")]);
+        let second = custom_format(&[("40..60", "mid"), ("0..50", "low"), ("..100", "any")]);
+        for _ in 0..10 {
+            assert_eq!(first.replace("45"), "low");
+            assert_eq!(second.replace("45"), "mid");
+        }
+        assert_eq!(first.replace("-5"), "any");
+    }
+
+    #[test]
+    fn circular_source_is_reported() {
+        let hm: HashMap<String, TccCustomFormat> = [source("a", Some("b|upper")), source("b", Some("a")), source("c", Some("cpu"))].into_iter().collect();
+        let err = check_custom_format_source(&hm).unwrap_err().to_string();
+        assert!(err.contains("a -> b -> a"), "{err}");
+        let hm: HashMap<String, TccCustomFormat> = [source("a", Some("a"))].into_iter().collect();
+        assert!(check_custom_format_source(&hm).is_err());
+        let hm: HashMap<String, TccCustomFormat> = [source("face", Some("cpu")), source("w", None), source("x", Some("face"))].into_iter().collect();
+        assert!(check_custom_format_source(&hm).is_ok());
+    }
+}
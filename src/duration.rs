@@ -0,0 +1,127 @@
+use chrono::*;
+
+// 経過時間・残り時間のフォーマット
+//   "d" / "h" / "m" / "s"  => 合計の日数 / 時間 / 分 / 秒
+//   %D 合計日数, %H 時 (00-23), %M 分 (00-59), %S 秒 (00-59)
+//   %h 合計時間, %m 合計分, %s 合計秒, %-H %-M %-S 0詰めなし
+//   %+ 符号 (+/-), %% => %
+// マイナスの場合、%+ がなければ先頭に - を付ける
+pub const DEFAULT_DURATION_FORMAT: &str = "%h:%M:%S";
+
+pub fn format_duration(seconds: i64, fmt: &str) -> String {
+    let fmt = if fmt.is_empty() { DEFAULT_DURATION_FORMAT } else { fmt };
+    let negative = seconds < 0;
+    let total = seconds.unsigned_abs();
+    let sign = if negative { "-" } else { "" };
+    match fmt {
+        "d" => return format!("{sign}{}", total / 86400),
+        "h" => return format!("{sign}{}", total / 3600),
+        "m" => return format!("{sign}{}", total / 60),
+        "s" => return format!("{sign}{total}"),
+        _ => {}
+    }
+    let mut result = String::new();
+    let mut has_sign = false;
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        let no_padding = chars.peek() == Some(&'-');
+        if no_padding {
+            chars.next();
+        }
+        let two_digits = |x: u64| if no_padding { x.to_string() } else { format!("{x:02}") };
+        match chars.next() {
+            Some('D') => result += &(total / 86400).to_string(),
+            Some('H') => result += &two_digits(total / 3600 % 24),
+            Some('M') => result += &two_digits(total / 60 % 60),
+            Some('S') => result += &two_digits(total % 60),
+            Some('h') => result += &(total / 3600).to_string(),
+            Some('m') => result += &(total / 60).to_string(),
+            Some('s') => result += &total.to_string(),
+            Some('+') => {
+                has_sign = true;
+                result += if negative { "-" } else { "+" };
+            }
+            Some('%') if !no_padding => result.push('%'),
+            // 知らない指定はそのまま残す
+            x => {
+                result.push('%');
+                if no_padding {
+                    result.push('-');
+                }
+                result.extend(x);
+            }
+        }
+    }
+    if negative && !has_sign {
+        result.insert(0, '-');
+    }
+    result
+}
+
+// "2026-12-31T00:00:00+09:00"  RFC3339
+// "2026-12-31 00:00:00", "2026-12-31T00:00", "2026-12-31"  => tz の日時
+pub fn parse_datetime<T: TimeZone>(s: &str, tz: &T) -> Option<DateTime<Utc>> {
+    let s = s.trim();
+    if let std::result::Result::Ok(x) = DateTime::parse_from_rfc3339(s) {
+        return Some(x.with_timezone(&Utc));
+    }
+    let s = s.replace('T', " ").replace('/', "-");
+    let naive = NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M"))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(&s, "%Y-%m-%d").ok().and_then(|x| x.and_hms_opt(0, 0, 0)))?;
+    tz.from_local_datetime(&naive).earliest().map(|x| x.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter;
+
+    #[test]
+    fn format_duration_over_24_hours() {
+        let seconds = 2 * 86400 + 3 * 3600 + 4 * 60 + 5;
+        assert_eq!(format_duration(seconds, ""), "51:04:05");
+        assert_eq!(format_duration(seconds, "%D日 %H:%M:%S"), "2日 03:04:05");
+        assert_eq!(format_duration(seconds, "%-H時間%-M分"), "3時間4分");
+        assert_eq!(format_duration(seconds, "%m分 %s秒"), "3064分 183845秒");
+        assert_eq!(format_duration(seconds, "d"), "2");
+        assert_eq!(format_duration(seconds, "h"), "51");
+    }
+
+    #[test]
+    fn format_duration_negative() {
+        assert_eq!(format_duration(-3661, ""), "-1:01:01");
+        assert_eq!(format_duration(-3661, "%+%h:%M"), "-1:01");
+        assert_eq!(format_duration(3661, "%+%h:%M"), "+1:01");
+        assert_eq!(format_duration(-90061, "%D %H:%M:%S"), "-1 01:01:01");
+        assert_eq!(format_duration(-59, "m"), "-0");
+        assert_eq!(format_duration(-59, "s"), "-59");
+    }
+
+    #[test]
+    fn format_duration_keeps_unknown_specifiers() {
+        assert_eq!(format_duration(61, "%M%%"), "01%");
+        assert_eq!(format_duration(61, "%Q %-Q %-% 100%"), "%Q %-Q %-% 100%");
+        assert_eq!(format_duration(61, "残り%-"), "残り%-");
+        // 結果に % が残っても strftime として読まれない
+        let now = Utc.with_ymd_and_hms(2024, 3, 9, 12, 34, 56).unwrap();
+        let label = filter::format_label("{x} %H", &now, |_| false, |_| format_duration(61, "%S%% %Q%"), |_, err| panic!("{err}"));
+        assert_eq!(label, "01% %Q% 12");
+    }
+
+    #[test]
+    fn parse_datetime_formats() {
+        let tz = FixedOffset::east_opt(9 * 3600).unwrap();
+        let expected = Utc.with_ymd_and_hms(2026, 12, 30, 15, 0, 0).unwrap();
+        assert_eq!(parse_datetime("2026-12-31T00:00:00+09:00", &tz), Some(expected));
+        assert_eq!(parse_datetime("2026-12-31 00:00:00", &tz), Some(expected));
+        assert_eq!(parse_datetime("2026/12/31T00:00", &tz), Some(expected));
+        assert_eq!(parse_datetime(" 2026-12-31 ", &tz), Some(expected));
+        assert_eq!(parse_datetime("2026-13-01", &tz), None);
+    }
+}
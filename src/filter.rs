@@ -0,0 +1,403 @@
+use std::{
+    collections::HashMap,
+    fmt,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use anyhow::*;
+use chrono::{DateTime, TimeZone};
+
+// {name|filter|filter(arg, 'arg')}
+// quoted strings may contain "{", "}", "|", "," and ")"
+pub static GLOBAL_PLACEHOLDER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\{((?:'[^']*'|"[^"]*"|[^{}'"])*)\}"#).unwrap());
+
+#[derive(Debug, Default, Clone)]
+pub struct TccFilterCall {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TccPlaceholder {
+    pub name: String,
+    pub format: String,
+    pub filters: Vec<TccFilterCall>,
+}
+
+pub struct TccFilter {
+    pub min_args: usize,
+    pub max_args: usize,
+    pub func: fn(&str, &[String]) -> anyhow::Result<String>,
+}
+
+// add new filters here
+pub static GLOBAL_TCC_FILTER: Lazy<HashMap<&'static str, TccFilter>> = Lazy::new(|| {
+    let mut hm: HashMap<&'static str, TccFilter> = HashMap::new();
+    hm.insert("upper", TccFilter { min_args: 0, max_args: 0, func: filter_upper });
+    hm.insert("lower", TccFilter { min_args: 0, max_args: 0, func: filter_lower });
+    hm.insert("trim", TccFilter { min_args: 0, max_args: 0, func: filter_trim });
+    hm.insert("pad", TccFilter { min_args: 1, max_args: 2, func: filter_pad });
+    hm.insert("truncate", TccFilter { min_args: 1, max_args: 2, func: filter_truncate });
+    hm.insert("replace", TccFilter { min_args: 2, max_args: 2, func: filter_replace });
+    hm.insert("map", TccFilter { min_args: 2, max_args: usize::MAX, func: filter_map });
+    hm.insert("wrap", TccFilter { min_args: 2, max_args: 2, func: filter_wrap });
+    hm.insert("fullwidth", TccFilter { min_args: 0, max_args: 0, func: filter_fullwidth });
+    hm
+});
+
+fn filter_upper(value: &str, _: &[String]) -> anyhow::Result<String> {
+    Ok(value.to_uppercase())
+}
+
+fn filter_lower(value: &str, _: &[String]) -> anyhow::Result<String> {
+    Ok(value.to_lowercase())
+}
+
+fn filter_trim(value: &str, _: &[String]) -> anyhow::Result<String> {
+    Ok(value.trim().to_string())
+}
+
+// pad の最大幅
+const MAX_PAD_WIDTH: u64 = 256;
+
+// pad(3) => right align, pad(-3) => left align, pad(3, '0') => zero fill
+fn filter_pad(value: &str, args: &[String]) -> anyhow::Result<String> {
+    let width: i64 = match args[0].trim().parse() {
+        Err(_) => return Err(anyhow!("pad : width is not number")),
+        std::result::Result::Ok(x) => x,
+    };
+    if width.unsigned_abs() > MAX_PAD_WIDTH {
+        return Err(anyhow!("pad : width is larger than {MAX_PAD_WIDTH}"));
+    }
+    let fill = match args.get(1) {
+        None => ' ',
+        Some(x) => {
+            let mut chars = x.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => c,
+                _ => return Err(anyhow!("pad : fill is not 1 character")),
+            }
+        }
+    };
+    let len = value.chars().count();
+    let width_abs = width.unsigned_abs() as usize;
+    if len >= width_abs {
+        return Ok(value.to_string());
+    }
+    let padding: String = std::iter::repeat_n(fill, width_abs - len).collect();
+    if width < 0 {
+        Ok(value.to_string() + &padding)
+    } else {
+        Ok(padding + value)
+    }
+}
+
+// truncate(5) / truncate(5, '…')
+fn filter_truncate(value: &str, args: &[String]) -> anyhow::Result<String> {
+    let width: usize = match args[0].trim().parse() {
+        Err(_) => return Err(anyhow!("truncate : length is not number")),
+        std::result::Result::Ok(x) => x,
+    };
+    if value.chars().count() <= width {
+        return Ok(value.to_string());
+    }
+    let suffix = args.get(1).map(|x| x.as_str()).unwrap_or("");
+    let keep = width.saturating_sub(suffix.chars().count());
+    Ok(value.chars().take(keep).collect::<String>() + suffix)
+}
+
+fn filter_replace(value: &str, args: &[String]) -> anyhow::Result<String> {
+    if args[0].is_empty() {
+        return Err(anyhow!("replace : from is empty"));
+    }
+    Ok(value.replace(&args[0], &args[1]))
+}
+
+// map('0', '日', '1', '月', '_', 'other')  "_" is the same as custom_formats > replace
+fn filter_map(value: &str, args: &[String]) -> anyhow::Result<String> {
+    if !args.len().is_multiple_of(2) {
+        return Err(anyhow!("map : arguments are not pairs"));
+    }
+    let mut other = None;
+    for pair in args.chunks(2) {
+        if pair[0] == value {
+            return Ok(pair[1].to_string());
+        }
+        if pair[0] == "_" {
+            other = Some(pair[1].to_string());
+        }
+    }
+    Ok(other.unwrap_or_else(|| value.to_string()))
+}
+
+fn filter_wrap(value: &str, args: &[String]) -> anyhow::Result<String> {
+    Ok(args[0].to_string() + value + &args[1])
+}
+
+fn filter_fullwidth(value: &str, _: &[String]) -> anyhow::Result<String> {
+    Ok(value.chars().map(|c| {
+        match c {
+            ' ' => '\u{3000}',
+            '!'..='~' => char::from_u32(c as u32 - 0x21 + 0xFF01).unwrap_or(c),
+            _ => c,
+        }
+    }).collect())
+}
+
+// split by separator, ignoring separators in quotes and parentheses
+fn split_top_level(s: &str, separator: char) -> anyhow::Result<Vec<String>> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut depth = 0;
+    for c in s.chars() {
+        match quote {
+            Some(q) => {
+                if c == q {
+                    quote = None;
+                }
+                current.push(c);
+            }
+            None => {
+                match c {
+                    '\'' | '"' => {
+                        quote = Some(c);
+                        current.push(c);
+                    }
+                    '(' => {
+                        depth += 1;
+                        current.push(c);
+                    }
+                    ')' => {
+                        depth -= 1;
+                        current.push(c);
+                    }
+                    _ if c == separator && depth == 0 => {
+                        result.push(current);
+                        current = String::new();
+                    }
+                    _ => {
+                        current.push(c);
+                    }
+                }
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(anyhow!("unclosed quote : {s}"));
+    }
+    if depth != 0 {
+        return Err(anyhow!("unclosed parenthesis : {s}"));
+    }
+    result.push(current);
+    Ok(result)
+}
+
+// ' abc ' => "abc", "'a b'" => "a b"  (filter args, gpu:name='...', proc:name~'...')
+pub(crate) fn unquote(s: &str) -> &str {
+    let s = s.trim();
+    for quote in ['"', '\''] {
+        if let Some(x) = s.strip_prefix(quote).and_then(|x| x.strip_suffix(quote)) {
+            return x;
+        }
+    }
+    s
+}
+
+fn parse_filter_call(s: &str) -> anyhow::Result<TccFilterCall> {
+    let s = s.trim();
+    let mut filter_call = TccFilterCall::default();
+    match s.find('(') {
+        None => {
+            filter_call.name = s.to_string();
+        }
+        Some(pos) => {
+            if !s.ends_with(')') {
+                return Err(anyhow!("incorrect filter : {s}"));
+            }
+            filter_call.name = s[..pos].trim().to_string();
+            let args = &s[pos + 1..s.len() - 1];
+            if !args.trim().is_empty() {
+                for arg in split_top_level(args, ',')? {
+                    filter_call.args.push(unquote(&arg).to_string());
+                }
+            }
+        }
+    }
+    match GLOBAL_TCC_FILTER.get(filter_call.name.as_str()) {
+        None => {
+            Err(anyhow!("unknown filter : {}", filter_call.name))
+        }
+        Some(filter) => {
+            if filter_call.args.len() < filter.min_args || filter_call.args.len() > filter.max_args {
+                return Err(anyhow!("incorrect number of arguments : {s}"));
+            }
+            // check arguments
+            (filter.func)("", &filter_call.args)?;
+            Ok(filter_call)
+        }
+    }
+}
+
+// "cpu|pad(3)|fullwidth" => name: "cpu", filters: [pad(3), fullwidth]
+// "until:release|d|pad(3)" => name: "until:release", format: "d", filters: [pad(3)]
+//   takes_format(name) が true の placeholder は、最初の "|" の次がフィルタでなければ format  {sunrise|%H時%M分}
+pub fn parse_placeholder(s: &str, takes_format: fn(&str) -> bool) -> anyhow::Result<TccPlaceholder> {
+    let mut stages = split_top_level(s, '|')?.into_iter().peekable();
+    let mut placeholder = TccPlaceholder {
+        name: stages.next().unwrap_or_default(),
+        ..Default::default()
+    };
+    if takes_format(&placeholder.name) {
+        if let Some(stage) = stages.peek() {
+            let filter_name = stage.split('(').next().unwrap_or("").trim();
+            if !GLOBAL_TCC_FILTER.contains_key(filter_name) {
+                placeholder.format = stages.next().unwrap_or_default();
+            }
+        }
+    }
+    for stage in stages {
+        placeholder.filters.push(parse_filter_call(&stage)?);
+    }
+    Ok(placeholder)
+}
+
+// 失敗したフィルタがあれば Err  (どのフィルタか分かるように。値は入れない)
+pub fn apply_filters(value: String, filters: &[TccFilterCall]) -> anyhow::Result<String> {
+    let mut value = value;
+    for filter_call in filters {
+        let Some(filter) = GLOBAL_TCC_FILTER.get(filter_call.name.as_str()) else {
+            return Err(anyhow!("unknown filter : {}", filter_call.name));
+        };
+        value = (filter.func)(&value, &filter_call.args)?;
+    }
+    Ok(value)
+}
+
+// strftime  (正しくない % があれば None)
+pub fn format_strftime<T: TimeZone>(now: &DateTime<T>, fmt: &str) -> Option<String> where T::Offset: fmt::Display {
+    let mut s = String::new();
+    match fmt::Write::write_fmt(&mut s, format_args!("{}", now.format(fmt))) {
+        Err(_) => None,
+        std::result::Result::Ok(_) => Some(s),
+    }
+}
+
+// label format => text
+//   {placeholder} を置き換えてから strftime する。置き換えた値の "%" は "%%" にして strftime に読ませない
+//   format に正しくない % があるときは strftime しない
+//   読めない {placeholder} は "" にして on_error に渡す
+pub fn format_label<T: TimeZone, F: Fn(&TccPlaceholder) -> String, E: Fn(&str, &anyhow::Error)>(format: &str, now: &DateTime<T>, takes_format: fn(&str) -> bool, get_value: F, on_error: E) -> String where T::Offset: fmt::Display {
+    let mut values: Vec<String> = Vec::new();
+    let escaped = GLOBAL_PLACEHOLDER_REGEX.replace_all(format, |caps: &regex::Captures| {
+        let value = match parse_placeholder(&caps[1], takes_format) {
+            Err(err) => {
+                on_error(&caps[1], &err);
+                "".to_string()
+            }
+            std::result::Result::Ok(placeholder) => get_value(&placeholder),
+        };
+        let escaped = value.replace('%', "%%");
+        values.push(value);
+        escaped
+    });
+    if let Some(x) = format_strftime(now, &escaped) {
+        return x;
+    }
+    let mut values = values.into_iter();
+    GLOBAL_PLACEHOLDER_REGEX.replace_all(format, |_: &regex::Captures| values.next().unwrap_or_default()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 9, 12, 34, 56).unwrap()
+    }
+
+    fn takes_format(name: &str) -> bool {
+        name.starts_with("until:") || name == "sunrise"
+    }
+
+    fn value_of(placeholder: &TccPlaceholder) -> String {
+        let value = match placeholder.name.as_str() {
+            "cpu" => "50%",
+            "host" => "lab%Y%",
+            "bad" => "%Q%",
+            "%A" => "Saturday",
+            _ => "",
+        };
+        apply_filters(value.to_string(), &placeholder.filters).unwrap()
+    }
+
+    fn no_error(placeholder: &str, err: &anyhow::Error) {
+        panic!("{{{placeholder}}} {err}");
+    }
+
+    #[test]
+    fn format_label_does_not_reparse_values_as_strftime() {
+        assert_eq!(format_label("%H:%M {cpu}", &now(), takes_format, value_of, no_error), "12:34 50%");
+        assert_eq!(format_label("{host} %Y", &now(), takes_format, value_of, no_error), "lab%Y% 2024");
+        assert_eq!(format_label("{bad}", &now(), takes_format, value_of, no_error), "%Q%");
+        assert_eq!(format_label("{%A|upper} 100%%", &now(), takes_format, value_of, no_error), "SATURDAY 100%");
+    }
+
+    #[test]
+    fn format_label_keeps_text_when_format_is_not_strftime() {
+        assert_eq!(format_label("%Q {cpu}", &now(), takes_format, value_of, no_error), "%Q 50%");
+        assert_eq!(format_label("50% {host}", &now(), takes_format, value_of, no_error), "50% lab%Y%");
+    }
+
+    #[test]
+    fn format_label_reports_placeholder_errors() {
+        let errors = std::cell::RefCell::new(Vec::new());
+        let text = format_label("[{cpu|nothing}] {cpu}", &now(), takes_format, value_of, |placeholder, err| errors.borrow_mut().push(format!("{placeholder} {err}")));
+        assert_eq!(text, "[] 50%");
+        assert_eq!(errors.into_inner(), vec!["cpu|nothing unknown filter : nothing".to_string()]);
+    }
+
+    #[test]
+    fn format_is_read_only_for_format_placeholders() {
+        let placeholder = parse_placeholder("until:release|%D日|pad(5)", takes_format).unwrap();
+        assert_eq!((placeholder.name.as_str(), placeholder.format.as_str(), placeholder.filters.len()), ("until:release", "%D日", 1));
+        let placeholder = parse_placeholder("sunrise|upper", takes_format).unwrap();
+        assert_eq!((placeholder.format.as_str(), placeholder.filters.len()), ("", 1));
+        assert!(parse_placeholder("cpu|%D日", takes_format).is_err());
+    }
+
+    #[test]
+    fn apply_filters_reports_errors() {
+        let placeholder = parse_placeholder("cpu|upper|pad(3)", takes_format).unwrap();
+        assert_eq!(apply_filters("7".to_string(), &placeholder.filters).unwrap(), "  7");
+        let filters = vec![TccFilterCall { name: "pad".to_string(), args: vec!["x".to_string()] }];
+        // 値は入れない (値ごとに違うメッセージになってログが増えないように)
+        for value in ["7", "8"] {
+            assert_eq!(apply_filters(value.to_string(), &filters).unwrap_err().to_string(), "pad : width is not number");
+        }
+        let filters = vec![TccFilterCall { name: "nothing".to_string(), args: Vec::new() }];
+        assert!(apply_filters("7".to_string(), &filters).is_err());
+    }
+
+    #[test]
+    fn unquote_strips_matching_quotes() {
+        assert_eq!(unquote(" 'a b' "), "a b");
+        assert_eq!(unquote("\"RTX 4070\""), "RTX 4070");
+        assert_eq!(unquote(" abc "), "abc");
+        assert_eq!(unquote("'abc\""), "'abc\"");
+        assert_eq!(unquote("'"), "'");
+        assert_eq!(unquote("''"), "");
+        let placeholder = parse_placeholder("cpu|wrap('[ ', \" ]\")", takes_format).unwrap();
+        assert_eq!(placeholder.filters[0].args, vec!["[ ", " ]"]);
+    }
+
+    #[test]
+    fn pad_width_is_limited() {
+        assert_eq!(filter_pad("ab", &["-4".to_string(), "*".to_string()]).unwrap(), "ab**");
+        assert_eq!(filter_pad("ab", &["256".to_string()]).unwrap().chars().count(), 256);
+        assert!(filter_pad("ab", &["257".to_string()]).is_err());
+        assert!(filter_pad("ab", &[i64::MIN.to_string()]).is_err());
+        assert!(parse_placeholder("cpu|pad(-9223372036854775808)", takes_format).is_err());
+    }
+}
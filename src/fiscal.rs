@@ -0,0 +1,114 @@
+use chrono::*;
+
+// 年度  start_month: 4 => 2024-04-01 ～ 2025-03-31 が 2024年度
+#[derive(Debug, Clone)]
+pub struct TccFiscalYear {
+    pub start_month: u32,
+    pub week_start: Weekday,
+}
+
+impl Default for TccFiscalYear {
+    fn default() -> Self {
+        TccFiscalYear {
+            start_month: 4,
+            week_start: Weekday::Mon,
+        }
+    }
+}
+
+impl TccFiscalYear {
+    pub fn get_year(&self, date: NaiveDate) -> i32 {
+        if date.month() >= self.start_month {
+            date.year()
+        } else {
+            date.year() - 1
+        }
+    }
+
+    // 1 ～ 4
+    pub fn get_quarter(&self, date: NaiveDate) -> u32 {
+        (date.month() + 12 - self.start_month) % 12 / 3 + 1
+    }
+
+    pub fn get_start_date(&self, date: NaiveDate) -> NaiveDate {
+        NaiveDate::from_ymd_opt(self.get_year(date), self.start_month, 1).unwrap()
+    }
+
+    // 年度の最初の日を含む週が第1週  週は week_start から始まる
+    pub fn get_week(&self, date: NaiveDate) -> u32 {
+        let start_date = self.get_start_date(date);
+        let offset = (start_date.weekday().num_days_from_monday() + 7 - self.week_start.num_days_from_monday()) as i64 % 7;
+        ((date - start_date).num_days() + offset) as u32 / 7 + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn fiscal(start_month: u32, week_start: Weekday) -> TccFiscalYear {
+        TccFiscalYear { start_month, week_start }
+    }
+
+    #[test]
+    fn first_and_last_day_of_fiscal_year() {
+        let x = TccFiscalYear::default();
+        assert_eq!(x.get_year(ymd(2025, 3, 31)), 2024);
+        assert_eq!(x.get_quarter(ymd(2025, 3, 31)), 4);
+        assert_eq!(x.get_start_date(ymd(2025, 3, 31)), ymd(2024, 4, 1));
+        assert_eq!(x.get_year(ymd(2025, 4, 1)), 2025);
+        assert_eq!(x.get_quarter(ymd(2025, 4, 1)), 1);
+        assert_eq!(x.get_week(ymd(2025, 4, 1)), 1);
+        assert_eq!(x.get_quarter(ymd(2024, 6, 30)), 1);
+        assert_eq!(x.get_quarter(ymd(2024, 7, 1)), 2);
+        assert_eq!(x.get_quarter(ymd(2025, 1, 1)), 4);
+    }
+
+    #[test]
+    fn week_53_in_leap_year() {
+        // 2023-04-01 は土曜日  2024-03-31 (日) は第53週
+        let x = TccFiscalYear::default();
+        assert_eq!(x.get_week(ymd(2023, 4, 1)), 1);
+        assert_eq!(x.get_week(ymd(2023, 4, 2)), 1);
+        assert_eq!(x.get_week(ymd(2023, 4, 3)), 2);
+        assert_eq!(x.get_week(ymd(2024, 3, 24)), 52);
+        assert_eq!(x.get_week(ymd(2024, 3, 25)), 53);
+        assert_eq!(x.get_week(ymd(2024, 3, 31)), 53);
+        // 日曜日始まりなら 2023-04-01 だけで第1週  2024-03-31 は第54週
+        let x = fiscal(4, Weekday::Sun);
+        assert_eq!(x.get_week(ymd(2023, 4, 2)), 2);
+        assert_eq!(x.get_week(ymd(2024, 3, 30)), 53);
+        assert_eq!(x.get_week(ymd(2024, 3, 31)), 54);
+    }
+
+    #[test]
+    fn start_month_january() {
+        let x = fiscal(1, Weekday::Mon);
+        assert_eq!(x.get_year(ymd(2024, 1, 1)), 2024);
+        assert_eq!(x.get_year(ymd(2024, 12, 31)), 2024);
+        assert_eq!(x.get_quarter(ymd(2024, 3, 31)), 1);
+        assert_eq!(x.get_quarter(ymd(2024, 12, 31)), 4);
+        assert_eq!(x.get_week(ymd(2024, 1, 1)), 1);
+        assert_eq!(x.get_week(ymd(2024, 12, 31)), 53);
+    }
+
+    #[test]
+    fn start_month_december() {
+        let x = fiscal(12, Weekday::Mon);
+        assert_eq!(x.get_year(ymd(2024, 11, 30)), 2023);
+        assert_eq!(x.get_quarter(ymd(2024, 11, 30)), 4);
+        assert_eq!(x.get_year(ymd(2024, 12, 1)), 2024);
+        assert_eq!(x.get_quarter(ymd(2024, 12, 1)), 1);
+        assert_eq!(x.get_year(ymd(2025, 2, 28)), 2024);
+        assert_eq!(x.get_quarter(ymd(2025, 2, 28)), 1);
+        assert_eq!(x.get_quarter(ymd(2025, 3, 1)), 2);
+        assert_eq!(x.get_start_date(ymd(2025, 2, 28)), ymd(2024, 12, 1));
+        // 2024-12-01 は日曜日
+        assert_eq!(x.get_week(ymd(2024, 12, 1)), 1);
+        assert_eq!(x.get_week(ymd(2024, 12, 2)), 2);
+    }
+}
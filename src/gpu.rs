@@ -0,0 +1,394 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use once_cell::sync::Lazy;
+use regex::RegexBuilder;
+use crate::filter;
+
+// GPU 使用率  config.txt > gpu
+//   aggregation : "max" => エンジンごとの最大 (タスクマネージャーと同じ)
+//                 "sum" => すべてのエンジンの合計 (100 で切る)
+//   aliases     : "dgpu": "name~RTX"  => {gpu:dgpu}
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TccGpuAggregation {
+    #[default]
+    Max,
+    Sum,
+}
+
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct TccGpuSetting {
+    pub aggregation: TccGpuAggregation,
+    pub aliases: HashMap<String, String>,
+}
+
+// 今の GPU の一覧 (gpu index 順)  アダプタが増減すると TccGpuSource が作り直す
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct TccGpuAdapter {
+    pub name: String,
+    pub luid: String,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+pub static GLOBAL_GPU_ADAPTER: Lazy<Mutex<Vec<TccGpuAdapter>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+// GPU の選び方
+//   name~"RTX"                       => 名前の正規表現 (大文字小文字は区別しない)
+//   name="NVIDIA GeForce RTX 4070"   => 名前
+//   luid=0x00000000_0x0000D1A2
+//   dgpu                             => config.txt > gpu > aliases
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub enum TccGpuSelector {
+    NameRegex(String),
+    Name(String),
+    Luid(String),
+}
+
+impl TccGpuSelector {
+    // alias は使えない
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn parse(s: &str) -> Option<TccGpuSelector> {
+        if let Some(x) = s.strip_prefix("name~") {
+            let pattern = filter::unquote(x);
+            RegexBuilder::new(pattern).case_insensitive(true).build().ok()?;
+            return Some(TccGpuSelector::NameRegex(pattern.to_string()));
+        }
+        if let Some(x) = s.strip_prefix("name=") {
+            return Some(TccGpuSelector::Name(filter::unquote(x).to_string()));
+        }
+        if let Some(x) = s.strip_prefix("luid=") {
+            return Some(TccGpuSelector::Luid(filter::unquote(x).to_string()));
+        }
+        None
+    }
+
+    // 最初に合った gpu index
+    pub fn find(&self, adapters: &[TccGpuAdapter]) -> Option<usize> {
+        match self {
+            TccGpuSelector::NameRegex(pattern) => {
+                let re = RegexBuilder::new(pattern).case_insensitive(true).build().ok()?;
+                adapters.iter().position(|x| re.is_match(&x.name))
+            }
+            TccGpuSelector::Name(name) => adapters.iter().position(|x| x.name.eq_ignore_ascii_case(name)),
+            TccGpuSelector::Luid(luid) => adapters.iter().position(|x| x.luid.eq_ignore_ascii_case(luid)),
+        }
+    }
+}
+
+// "gpu:name~'RTX':3d" => ("name~'RTX'", ":3d")  引用符の中の ":" では切らない
+#[cfg_attr(not(windows), allow(dead_code))]
+fn split_gpu_selector(s: &str) -> (&str, &str) {
+    let mut quote: Option<char> = None;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(x) if x == c => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ':' => return (&s[..i], &s[i..]),
+            None => {}
+        }
+    }
+    (s, "")
+}
+
+// {gpu:name~"RTX"}      => gpu0
+// {gpu:dgpu:3d}         => gpu0:3d
+// {gpu:dgpu:temp}       => gpu0_temp  (name, dedicated, shared, temp)
+// 合う GPU がない => Some(None), gpu: でない・書き方が違う => None
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn resolve_gpu_name(name: &str, aliases: &HashMap<String, String>, adapters: &[TccGpuAdapter]) -> Option<Option<String>> {
+    let (selector, field) = split_gpu_selector(name.strip_prefix("gpu:")?);
+    let selector = match aliases.get(selector) {
+        Some(x) => TccGpuSelector::parse(x)?,
+        None => TccGpuSelector::parse(selector)?,
+    };
+    let field = match field {
+        "" => "".to_string(),
+        ":name" | ":dedicated" | ":shared" | ":temp" => format!("_{}", &field[1..]),
+        x => x.to_string(),
+    };
+    Some(selector.find(adapters).map(|i| format!("gpu{i}{field}")))
+}
+
+// GPU Engine のインスタンス名
+//   "pid_1234_luid_0x00000000_0x0000D1A2_phys_0_eng_0_engtype_3D"
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TccGpuEngine {
+    pub pid: u32,
+    pub luid: String,  // "0x00000000_0x0000D1A2"
+    pub phys: u32,
+    pub eng: u32,
+    pub engtype: String,  // "3d", "copy", "video_decode", "compute_0"
+}
+
+// "VideoDecode" => "video_decode", "3D" => "3d", "Compute_0" => "compute_0"
+pub fn normalize_engtype(s: &str) -> String {
+    let mut normalized = String::new();
+    let mut last: Option<char> = None;
+    for c in s.trim().chars() {
+        if c == ' ' || c == '-' {
+            normalized.push('_');
+        } else {
+            if c.is_ascii_uppercase() && last.is_some_and(|x| x.is_ascii_lowercase()) {
+                normalized.push('_');
+            }
+            normalized.push(c.to_ascii_lowercase());
+        }
+        last = Some(c);
+    }
+    normalized
+}
+
+// "0x00000000_0x0000d1a2" => "0x00000000_0x0000D1A2"  16進8桁が2つでなければ None
+fn parse_luid(s: &str) -> Option<String> {
+    let (high, low) = s.split_once('_')?;
+    for x in [high, low] {
+        let digits = x.strip_prefix("0x").or(x.strip_prefix("0X"))?;
+        if digits.len() != 8 || !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+            return None;
+        }
+    }
+    Some(s.to_ascii_uppercase().replace("0X", "0x"))
+}
+
+impl TccGpuEngine {
+    pub fn parse(s: &str) -> Option<TccGpuEngine> {
+        let s = s.strip_prefix("pid_")?;
+        let (pid, s) = s.split_once("_luid_")?;
+        let (luid, s) = s.split_once("_phys_")?;
+        let (phys, s) = s.split_once("_eng_")?;
+        let (eng, engtype) = s.split_once("_engtype_")?;
+        let engtype = normalize_engtype(engtype);
+        if engtype.is_empty() {
+            return None;
+        }
+        Some(TccGpuEngine {
+            pid: pid.parse().ok()?,
+            luid: parse_luid(luid)?,
+            phys: phys.parse().ok()?,
+            eng: eng.parse().ok()?,
+            engtype,
+        })
+    }
+}
+
+// AdapterLuid (registry) => "0x00000000_0x0000D1A2"
+pub fn format_luid(luid: u64) -> String {
+    format!("0x{:08X}_0x{:08X}", luid >> 32, luid & 0xFFFF_FFFF)
+}
+
+// GPU Engine の値 => gpu0, gpu0:3d, gpu0:copy, ...
+//   luids : gpu index 順
+//   エンジン (luid, phys, eng) ごとにプロセスの値を合計して、Max ならエンジンの最大、Sum なら合計
+pub fn get_gpu_values(instances: &[(String, f64)], luids: &[String], aggregation: TccGpuAggregation) -> Vec<(String, f64)> {
+    // (gpu index, phys, eng) => (engtype, value)
+    let mut engines: HashMap<(usize, u32, u32), (String, f64)> = HashMap::new();
+    for (name, value) in instances {
+        let Some(engine) = TccGpuEngine::parse(name) else {
+            continue;
+        };
+        let Some(i) = luids.iter().position(|x| x.eq_ignore_ascii_case(&engine.luid)) else {
+            continue;
+        };
+        engines.entry((i, engine.phys, engine.eng)).or_insert((engine.engtype, 0.0)).1 += value.max(0.0);
+    }
+    let aggregate = |values: &mut dyn Iterator<Item = f64>| -> f64 {
+        let value = match aggregation {
+            TccGpuAggregation::Max => values.reduce(f64::max).unwrap_or(0.0),
+            TccGpuAggregation::Sum => values.sum(),
+        };
+        value.min(100.0).round()
+    };
+    let mut values: Vec<(String, f64)> = Vec::new();
+    for i in 0..luids.len() {
+        let gpu_engines: Vec<&(String, f64)> = engines.iter().filter(|x| x.0 .0 == i).map(|x| x.1).collect();
+        values.push((format!("gpu{i}"), aggregate(&mut gpu_engines.iter().map(|x| x.1))));
+        let mut engtypes: Vec<&str> = gpu_engines.iter().map(|x| x.0.as_str()).collect();
+        engtypes.sort();
+        engtypes.dedup();
+        for engtype in engtypes {
+            let value = aggregate(&mut gpu_engines.iter().filter(|x| x.0 == engtype).map(|x| x.1));
+            values.push((format!("gpu{i}:{engtype}"), value));
+        }
+    }
+    values
+}
+
+// "gpu0" => (0, ""), "gpu1:3d" => (1, ":3d"), "gpu0_name" => (0, "_name")
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn split_gpu_name(name: &str) -> Option<(usize, &str)> {
+    let s = name.strip_prefix("gpu")?;
+    let digits = s.chars().take_while(|x| x.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    Some((s[..digits].parse().ok()?, &s[digits..]))
+}
+
+// GPU Adapter Memory のインスタンス名  "luid_0x00000000_0x0000D1A2_phys_0" => "0x00000000_0x0000D1A2"
+pub fn parse_adapter_memory_instance(s: &str) -> Option<String> {
+    let (luid, _) = s.strip_prefix("luid_")?.split_once("_phys_")?;
+    parse_luid(luid)
+}
+
+// GPU Adapter Memory に出ている luid が変わったら GPU の一覧を作り直す (WMI)
+// 作り直せなかった luid の組は GPU_REBUILD_RETRY_INTERVAL の間は試さない  (毎回 WMI を呼ばない)
+const GPU_REBUILD_RETRY_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default, Clone)]
+pub struct TccGpuLuidWatcher {
+    // 今の一覧を作ったときの luid
+    luids: Option<Vec<String>>,
+    // 作り直せなかった luid と時刻
+    failed: Option<(Vec<String>, Instant)>,
+}
+
+impl TccGpuLuidWatcher {
+    // 作り直す => true  (結果は set_result で渡す)  最初の1回は今の一覧のまま
+    pub fn is_changed(&mut self, luids: &[String], now: Instant) -> bool {
+        match &self.luids {
+            None => {
+                self.luids = Some(luids.to_vec());
+                false
+            }
+            Some(x) if x == luids => false,
+            _ => !self.failed.as_ref().is_some_and(|(x, time)| x == luids && now.saturating_duration_since(*time) < GPU_REBUILD_RETRY_INTERVAL),
+        }
+    }
+
+    pub fn set_result(&mut self, luids: &[String], is_ok: bool, now: Instant) {
+        if is_ok {
+            self.luids = Some(luids.to_vec());
+            self.failed = None;
+        } else {
+            self.failed = Some((luids.to_vec(), now));
+        }
+    }
+}
+
+// GPU Adapter Memory の値 => gpu0_dedicated, gpu0_shared (bytes)  カウンタがない GPU は NaN
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn get_gpu_memory_values(dedicated: &[(String, f64)], shared: &[(String, f64)], luids: &[String]) -> Vec<(String, f64)> {
+    let mut values: Vec<(String, f64)> = Vec::new();
+    for (i, luid) in luids.iter().enumerate() {
+        for (name, instances) in [("dedicated", dedicated), ("shared", shared)] {
+            let matched: Vec<f64> = instances
+                .iter()
+                .filter(|x| parse_adapter_memory_instance(&x.0).is_some_and(|x| x.eq_ignore_ascii_case(luid)))
+                .map(|x| x.1)
+                .collect();
+            let value = if matched.is_empty() { f64::NAN } else { matched.iter().sum() };
+            values.push((format!("gpu{i}_{name}"), value));
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(pid: u32, luid: &str, phys: u32, eng: u32, engtype: &str) -> Option<TccGpuEngine> {
+        Some(TccGpuEngine { pid, luid: luid.to_string(), phys, eng, engtype: engtype.to_string() })
+    }
+
+    #[test]
+    fn parse_gpu_engine_instances() {
+        assert_eq!(
+            TccGpuEngine::parse("pid_13916_luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_3D"),
+            engine(13916, "0x00000000_0x0000F1E6", 0, 0, "3d")
+        );
+        assert_eq!(
+            TccGpuEngine::parse("pid_4_luid_0x00000000_0x0000c4d8_phys_0_eng_12_engtype_VideoDecode"),
+            engine(4, "0x00000000_0x0000C4D8", 0, 12, "video_decode")
+        );
+        assert_eq!(
+            TccGpuEngine::parse("pid_2596_luid_0x00000001_0x00010B0D_phys_1_eng_5_engtype_Compute_0"),
+            engine(2596, "0x00000001_0x00010B0D", 1, 5, "compute_0")
+        );
+        assert_eq!(
+            TccGpuEngine::parse("pid_0_luid_0x00000000_0x0000F1E6_phys_0_eng_7_engtype_Video Encode"),
+            engine(0, "0x00000000_0x0000F1E6", 0, 7, "video_encode")
+        );
+    }
+
+    #[test]
+    fn parse_malformed_gpu_engine_instances() {
+        for s in [
+            "",
+            "_Total",
+            "luid_0x00000000_0x0000F1E6_phys_0",
+            "pid__luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_3D",
+            "pid_12a_luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_3D",
+            "pid_-1_luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_3D",
+            "pid_1_luid__phys_0_eng_0_engtype_3D",
+            "pid_1_luid_0x0_0x1_phys_0_eng_0_engtype_3D",
+            "pid_1_luid_0x00000000_0x0000G1E6_phys_0_eng_0_engtype_3D",
+            "pid_1_luid_0x00000000_phys_0_eng_0_engtype_3D",
+            "pid_1_luid_0x00000000_0x0000F1E6_phys_x_eng_0_engtype_3D",
+            "pid_1_luid_0x00000000_0x0000F1E6_phys_0_eng__engtype_3D",
+            "pid_1_luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_",
+            "pid_1_luid_0x00000000_0x0000F1E6_phys_0_eng_0",
+        ] {
+            assert_eq!(TccGpuEngine::parse(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn gpu_values_per_engine() {
+        let luids = vec![format_luid(0xF1E6), format_luid(0x1_0001_0B0D)];
+        assert_eq!(luids[1], "0x00000001_0x00010B0D");
+        let instances: Vec<(String, f64)> = [
+            ("pid_1_luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_3D", 30.0),
+            ("pid_2_luid_0x00000000_0x0000F1E6_phys_0_eng_0_engtype_3D", 25.0),
+            ("pid_2_luid_0x00000000_0x0000F1E6_phys_0_eng_3_engtype_Copy", 10.0),
+            ("pid_3_luid_0x00000000_0x0000FFFF_phys_0_eng_0_engtype_3D", 99.0),
+            ("pid_3_luid_broken", 99.0),
+        ]
+        .iter()
+        .map(|(x, y)| (x.to_string(), *y))
+        .collect();
+        let values = get_gpu_values(&instances, &luids, TccGpuAggregation::Max);
+        assert_eq!(values, vec![
+            ("gpu0".to_string(), 55.0),
+            ("gpu0:3d".to_string(), 55.0),
+            ("gpu0:copy".to_string(), 10.0),
+            ("gpu1".to_string(), 0.0),
+        ]);
+        let values = get_gpu_values(&instances, &luids, TccGpuAggregation::Sum);
+        assert_eq!(values[0], ("gpu0".to_string(), 65.0));
+    }
+
+    #[test]
+    fn adapter_memory_instances() {
+        assert_eq!(parse_adapter_memory_instance("luid_0x00000000_0x0000f1e6_phys_0"), Some("0x00000000_0x0000F1E6".to_string()));
+        assert_eq!(parse_adapter_memory_instance("luid_0x00000000_phys_0"), None);
+        assert_eq!(parse_adapter_memory_instance("_Total"), None);
+    }
+
+    #[test]
+    fn luid_watcher_retries_failed_rebuild_later() {
+        let luids = |x: &[&str]| x.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+        let mut watcher = TccGpuLuidWatcher::default();
+        let start = Instant::now();
+        assert!(!watcher.is_changed(&luids(&["0x00000000_0x0000D1A2"]), start));
+        assert!(!watcher.is_changed(&luids(&["0x00000000_0x0000D1A2"]), start));
+        // eGPU をつないだ  WMI が失敗した
+        let added = luids(&["0x00000000_0x0000D1A2", "0x00000000_0x0001E2B3"]);
+        assert!(watcher.is_changed(&added, start));
+        watcher.set_result(&added, false, start);
+        assert!(!watcher.is_changed(&added, start + Duration::from_secs(1)));
+        assert!(!watcher.is_changed(&added, start + Duration::from_secs(59)));
+        // 別の組になったらすぐ試す
+        assert!(watcher.is_changed(&luids(&["0x00000000_0x0001E2B3"]), start + Duration::from_secs(2)));
+        assert!(watcher.is_changed(&added, start + Duration::from_secs(60)));
+        watcher.set_result(&added, true, start + Duration::from_secs(60));
+        assert!(!watcher.is_changed(&added, start + Duration::from_secs(61)));
+        assert!(watcher.is_changed(&luids(&["0x00000000_0x0000D1A2"]), start + Duration::from_secs(61)));
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+};
+use chrono::*;
+use anyhow::*;
+
+// 国民の祝日に関する法律 (1948/07/20～)
+
+// 春分日・秋分日  (1900～2150)
+//   int(x + 0.242194 * (year - 1980) - int((year - leap_base) / 4))  int は 0 方向への切り捨て
+pub fn get_equinox_day(year: i32, is_spring: bool) -> Option<u32> {
+    let (leap_base, spring, autumn) = match year {
+        1900..=1979 => (1983, 20.8357, 23.2588),
+        1980..=2099 => (1980, 20.8431, 23.2488),
+        2100..=2150 => (1980, 21.8510, 24.2488),
+        _ => return None,
+    };
+    let x = if is_spring { spring } else { autumn };
+    let y = (year - 1980) as f64;
+    let leap = ((year - leap_base) as f64 / 4.0).trunc();
+    Some((x + 0.242194 * y - leap).floor() as u32)
+}
+
+// n番目の月曜日  (ハッピーマンデー)
+fn get_nth_monday(year: i32, month: u32, n: u8) -> Option<NaiveDate> {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Mon, n)
+}
+
+// 振替休日・国民の休日を含まない祝日
+fn get_national_holiday_name(date: NaiveDate) -> Option<&'static str> {
+    if date < NaiveDate::from_ymd_opt(1948, 7, 20).unwrap() {
+        return None;
+    }
+    let year = date.year();
+    let month = date.month();
+    let day = date.day();
+    let name = match (year, month, day) {
+        // 一度限りの休日
+        (1959, 4, 10) => "皇太子明仁親王の結婚の儀",
+        (1989, 2, 24) => "昭和天皇の大喪の礼",
+        (1990, 11, 12) => "即位礼正殿の儀",
+        (1993, 6, 9) => "皇太子徳仁親王の結婚の儀",
+        (2019, 5, 1) => "天皇の即位の日",
+        (2019, 10, 22) => "即位礼正殿の儀",
+        (_, 1, 1) => "元日",
+        (1949..=1999, 1, 15) => "成人の日",
+        (1967.., 2, 11) => "建国記念の日",
+        (2020.., 2, 23) => "天皇誕生日",
+        (1949..=1988, 4, 29) => "天皇誕生日",
+        (1989..=2006, 4, 29) => "みどりの日",
+        (2007.., 4, 29) => "昭和の日",
+        (1949.., 5, 3) => "憲法記念日",
+        (2007.., 5, 4) => "みどりの日",
+        (1949.., 5, 5) => "こどもの日",
+        (1996..=2002, 7, 20) => "海の日",
+        (2020, 7, 23) => "海の日",
+        (2021, 7, 22) => "海の日",
+        (2020, 7, 24) => "スポーツの日",
+        (2021, 7, 23) => "スポーツの日",
+        (2020, 8, 10) => "山の日",
+        (2021, 8, 8) => "山の日",
+        (2016..=2019, 8, 11) | (2022.., 8, 11) => "山の日",
+        (1966..=2002, 9, 15) => "敬老の日",
+        (1966..=1999, 10, 10) => "体育の日",
+        (1948.., 11, 3) => "文化の日",
+        (1948.., 11, 23) => "勤労感謝の日",
+        (1989..=2018, 12, 23) => "天皇誕生日",
+        _ => "",
+    };
+    if !name.is_empty() {
+        return Some(name);
+    }
+
+    if month == 7 && (year == 2020 || year == 2021) {
+        // 東京オリンピック・パラリンピックによる移動
+        return None;
+    }
+
+    // ハッピーマンデー
+    let happy_monday = [
+        (2000.., 1, 2, "成人の日"),
+        (2003.., 7, 3, "海の日"),
+        (2003.., 9, 3, "敬老の日"),
+    ];
+    for (years, m, n, name) in happy_monday {
+        if years.contains(&year) && month == m && get_nth_monday(year, m, n) == Some(date) {
+            return Some(name);
+        }
+    }
+    if month == 10 && get_nth_monday(year, 10, 2) == Some(date) {
+        match year {
+            2000..=2019 => return Some("体育の日"),
+            2022.. => return Some("スポーツの日"),
+            _ => {}
+        }
+    }
+
+    // 春分の日・秋分の日
+    if year >= 1949 && month == 3 && get_equinox_day(year, true) == Some(day) {
+        return Some("春分の日");
+    }
+    if month == 9 && get_equinox_day(year, false) == Some(day) {
+        return Some("秋分の日");
+    }
+    None
+}
+
+pub fn get_holiday_name(date: NaiveDate) -> Option<&'static str> {
+    if let Some(name) = get_national_holiday_name(date) {
+        return Some(name);
+    }
+
+    // 振替休日  1973/04/12～
+    if date >= NaiveDate::from_ymd_opt(1973, 4, 12).unwrap() {
+        if date.year() >= 2007 {
+            // 日曜日の祝日から続く祝日の翌日
+            let mut d = date.pred_opt()?;
+            while get_national_holiday_name(d).is_some() {
+                if d.weekday() == Weekday::Sun {
+                    return Some("振替休日");
+                }
+                d = d.pred_opt()?;
+            }
+        } else if date.weekday() == Weekday::Mon && get_national_holiday_name(date.pred_opt()?).is_some() {
+            return Some("振替休日");
+        }
+    }
+
+    // 国民の休日  1985/12/27～  祝日に挟まれた日
+    if date >= NaiveDate::from_ymd_opt(1985, 12, 27).unwrap()
+    && get_national_holiday_name(date.pred_opt()?).is_some()
+    && get_national_holiday_name(date.succ_opt()?).is_some() {
+        if date.year() >= 2007 {
+            return Some("国民の休日");
+        }
+        // 2006年までは日曜日を除く
+        if date.weekday() != Weekday::Sun {
+            return Some("国民の休日");
+        }
+    }
+    None
+}
+
+// 追加の休日  holiday_files
+//   2024-12-30 年末休暇    => その日だけ
+//   12-31 大晦日            => 毎年
+//   # コメント
+#[derive(Debug, Default, Clone)]
+pub struct TccHolidayCalendar {
+    dates: HashMap<NaiveDate, String>,
+    annual: HashMap<(u32, u32), String>,
+}
+
+impl TccHolidayCalendar {
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn load_file(&mut self, path: &Path) -> anyhow::Result<()> {
+        let text = fs::read_to_string(path)?;
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim().trim_start_matches('\u{feff}');
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (date, name) = match line.split_once(|c: char| c.is_whitespace() || c == ',') {
+                None => (line, ""),
+                Some((date, name)) => (date, name.trim()),
+            };
+            let date = date.replace('/', "-");
+            if let std::result::Result::Ok(d) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+                self.dates.insert(d, name.to_string());
+                continue;
+            }
+            match date.split_once('-').map(|(m, d)| (m.parse::<u32>(), d.parse::<u32>())) {
+                Some((std::result::Result::Ok(m), std::result::Result::Ok(d))) if NaiveDate::from_ymd_opt(2000, m, d).is_some() => {
+                    self.annual.insert((m, d), name.to_string());
+                }
+                _ => {
+                    return Err(anyhow!(format!("{} line {} : {line}", path.display(), i + 1)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, date: NaiveDate) -> Option<&str> {
+        self.dates.get(&date).or(self.annual.get(&(date.month(), date.day()))).map(|x| x.as_str())
+    }
+
+    // 祝日 + 追加の休日  名前がない日は"休日"
+    pub fn get_holiday_name(&self, date: NaiveDate) -> Option<&str> {
+        match get_holiday_name(date) {
+            Some(name) => Some(name),
+            None => self.get(date).map(|x| if x.is_empty() { "休日" } else { x }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn equinox_day_1949_1979() {
+        // 春分日は 1960,1964,...,1976 が 20日、秋分日は 1951,1955,...,1979 が 24日
+        for year in 1949..=1979 {
+            let spring = if year >= 1960 && year % 4 == 0 { 20 } else { 21 };
+            let autumn = if year % 4 == 3 { 24 } else { 23 };
+            assert_eq!(get_equinox_day(year, true), Some(spring), "{year}");
+            assert_eq!(get_equinox_day(year, false), Some(autumn), "{year}");
+        }
+        assert_eq!(get_holiday_name(ymd(1950, 9, 23)), Some("秋分の日"));
+        assert_eq!(get_holiday_name(ymd(1960, 3, 20)), Some("春分の日"));
+        assert_eq!(get_holiday_name(ymd(1979, 3, 21)), Some("春分の日"));
+        assert_eq!(get_holiday_name(ymd(1979, 9, 24)), Some("秋分の日"));
+        assert_eq!(get_holiday_name(ymd(1979, 9, 23)), None);
+    }
+
+    #[test]
+    fn equinox_day_1980_2099() {
+        let spring = [
+            (1980, 20), (1981, 21), (1992, 20), (1993, 20), (2000, 20), (2002, 21), (2012, 20), (2023, 21),
+            (2024, 20), (2025, 20), (2026, 20), (2027, 21), (2031, 21), (2035, 21), (2055, 21), (2056, 20),
+            (2059, 20), (2092, 19), (2096, 19), (2099, 20),
+        ];
+        for (year, day) in spring {
+            assert_eq!(get_equinox_day(year, true), Some(day), "{year}");
+        }
+        let autumn = [
+            (1980, 23), (2011, 23), (2012, 22), (2016, 22), (2019, 23), (2020, 22), (2023, 23), (2024, 22),
+            (2025, 23), (2044, 22), (2045, 22), (2047, 23), (2078, 22), (2079, 23), (2099, 23),
+        ];
+        for (year, day) in autumn {
+            assert_eq!(get_equinox_day(year, false), Some(day), "{year}");
+        }
+        assert_eq!(get_equinox_day(1899, true), None);
+        assert_eq!(get_equinox_day(2151, false), None);
+    }
+
+    #[test]
+    fn substitute_and_citizens_holidays() {
+        // 敬老の日と秋分の日に挟まれた日
+        assert_eq!(get_holiday_name(ymd(2009, 9, 22)), Some("国民の休日"));
+        // 昭和の日・即位の日・憲法記念日に挟まれた日
+        assert_eq!(get_holiday_name(ymd(2019, 4, 30)), Some("国民の休日"));
+        assert_eq!(get_holiday_name(ymd(2019, 5, 2)), Some("国民の休日"));
+        // 日曜日の祝日から続く祝日の翌日
+        assert_eq!(get_holiday_name(ymd(2019, 5, 6)), Some("振替休日"));
+        assert_eq!(get_holiday_name(ymd(2008, 5, 6)), Some("振替休日"));
+        // 2006年までは月曜日だけ
+        assert_eq!(get_holiday_name(ymd(1973, 4, 30)), Some("振替休日"));
+        assert_eq!(get_holiday_name(ymd(1973, 4, 12)), None);
+        // 2020年の体育の日 (スポーツの日) は 7/24 に移動
+        assert_eq!(get_holiday_name(ymd(2020, 10, 12)), None);
+        assert_eq!(get_holiday_name(ymd(2020, 7, 24)), Some("スポーツの日"));
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::*;
+use crate::astro;
+
+// 旧暦・六曜・二十四節気・干支
+// 日付の区切りは offset (UTCからの秒数) のタイムゾーンの0時
+
+const JIKKAN: [&str; 10] = ["甲", "乙", "丙", "丁", "戊", "己", "庚", "辛", "壬", "癸"];
+const JUNISHI: [&str; 12] = ["子", "丑", "寅", "卯", "辰", "巳", "午", "未", "申", "酉", "戌", "亥"];
+const ROKUYO: [&str; 6] = ["大安", "赤口", "先勝", "友引", "先負", "仏滅"];
+// 太陽黄経 0°, 15°, 30°, ...
+const SEKKI: [&str; 24] = [
+    "春分", "清明", "穀雨", "立夏", "小満", "芒種",
+    "夏至", "小暑", "大暑", "立秋", "処暑", "白露",
+    "秋分", "寒露", "霜降", "立冬", "小雪", "大雪",
+    "冬至", "小寒", "大寒", "立春", "雨水", "啓蟄",
+];
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TccKyureki {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub is_leap: bool,
+}
+
+impl TccKyureki {
+    pub fn to_string_jp(&self) -> String {
+        format!("{}{}月{}日", if self.is_leap { "閏" } else { "" }, self.month, self.day)
+    }
+}
+
+// 日付の0時 (JD)
+fn date_to_jd(date: NaiveDate, offset: i32) -> f64 {
+    let dt = date.and_hms_opt(0, 0, 0).unwrap() - Duration::seconds(offset as i64);
+    astro::julian_day(Utc.from_utc_datetime(&dt))
+}
+
+fn jd_to_date(jd: f64, offset: i32) -> NaiveDate {
+    (astro::from_julian_day(jd).naive_utc() + Duration::seconds(offset as i64)).date()
+}
+
+// その日を含む月の朔の k
+fn get_month_k(date: NaiveDate, offset: i32) -> f64 {
+    let next_day_jd = date_to_jd(date.succ_opt().unwrap_or(date), offset);
+    astro::new_moon_k_before(next_day_jd - 0.000001)
+}
+
+// 朔 k から始まる月に中気 (太陽黄経 30° ごと) が含まれるか
+fn has_chuki(k: f64, offset: i32) -> bool {
+    let start = date_to_jd(jd_to_date(astro::new_moon(k), offset), offset);
+    let end = date_to_jd(jd_to_date(astro::new_moon(k + 1.0), offset), offset);
+    let longitude = astro::sun_longitude(astro::to_jde(start));
+    let target = ((longitude / 30.0).floor() + 1.0) * 30.0;
+    let guess = start + (target - longitude) / 360.0 * 365.2422;
+    astro::solve_sun_longitude(target % 360.0, guess) < end
+}
+
+// year 年の冬至を含む月の朔の k
+fn get_touji_month_k(year: i32, offset: i32) -> f64 {
+    let guess = date_to_jd(NaiveDate::from_ymd_opt(year, 12, 21).unwrap(), offset);
+    let touji = jd_to_date(astro::solve_sun_longitude(270.0, guess), offset);
+    get_month_k(touji, offset)
+}
+
+// 旧暦  (天保暦の置閏法)
+//   冬至を含む月を11月とし、次の冬至を含む月までが13か月なら最初の中気を含まない月を閏月とする
+//   2033年のように冬至・春分・秋分の月が両立しない年は冬至と春分を優先する (閏11月)
+pub fn get_kyureki(date: NaiveDate, offset: i32) -> TccKyureki {
+    let k = get_month_k(date, offset);
+    let mut start_k = get_touji_month_k(date.year(), offset);
+    let mut end_k = get_touji_month_k(date.year() + 1, offset);
+    if start_k > k {
+        end_k = start_k;
+        start_k = get_touji_month_k(date.year() - 1, offset);
+    }
+    let leap_k = if end_k - start_k >= 13.0 {
+        (1..(end_k - start_k) as i32).map(|i| start_k + i as f64).find(|x| !has_chuki(*x, offset))
+    } else {
+        None
+    };
+    let mut n = (k - start_k) as u32;
+    if leap_k.is_some_and(|x| k >= x) {
+        n -= 1;
+    }
+    let start_date = jd_to_date(astro::new_moon(k), offset);
+    let mut kyureki = TccKyureki {
+        year: date.year(),
+        month: (n + 10) % 12 + 1,
+        day: (date - start_date).num_days() as u32 + 1,
+        is_leap: leap_k == Some(k),
+    };
+    if kyureki.month >= 11 && date.month() <= 2 {
+        kyureki.year -= 1;
+    }
+    kyureki
+}
+
+pub fn get_rokuyo(kyureki: &TccKyureki) -> &'static str {
+    ROKUYO[((kyureki.month + kyureki.day) % 6) as usize]
+}
+
+// 二十四節気  (その日の時点の節気, その日が節気の日か)
+pub fn get_sekki(date: NaiveDate, offset: i32) -> (&'static str, bool) {
+    let start = astro::sun_longitude(astro::to_jde(date_to_jd(date, offset)));
+    let end = astro::sun_longitude(astro::to_jde(date_to_jd(date.succ_opt().unwrap_or(date), offset)));
+    let index = (end / 15.0).floor() as usize % 24;
+    (SEKKI[index], (start / 15.0).floor() as usize % 24 != index)
+}
+
+// 年の干支  2024 => 甲辰
+pub fn get_kanshi_year(year: i32) -> String {
+    let i = (year - 4).rem_euclid(60) as usize;
+    JIKKAN[i % 10].to_string() + JUNISHI[i % 12]
+}
+
+// 年の十二支  2024 => 辰
+pub fn get_eto(year: i32) -> &'static str {
+    JUNISHI[(year - 4).rem_euclid(12) as usize]
+}
+
+// 日の干支  2000/01/01 => 戊午
+pub fn get_kanshi_day(date: NaiveDate) -> String {
+    let jdn = date.num_days_from_ce() as i64 + 1721425;
+    let i = (jdn + 49).rem_euclid(60) as usize;
+    JIKKAN[i % 10].to_string() + JUNISHI[i % 12]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JST: i32 = 9 * 3600;
+
+    fn kyureki(year: i32, month: u32, day: u32) -> TccKyureki {
+        get_kyureki(NaiveDate::from_ymd_opt(year, month, day).unwrap(), JST)
+    }
+
+    fn expected(year: i32, month: u32, day: u32, is_leap: bool) -> TccKyureki {
+        TccKyureki { year, month, day, is_leap }
+    }
+
+    #[test]
+    fn kyureki_leap_months() {
+        assert_eq!(kyureki(2012, 4, 21), expected(2012, 3, 1, true));
+        assert_eq!(kyureki(2012, 5, 21), expected(2012, 4, 1, false));
+        assert_eq!(kyureki(2014, 10, 24), expected(2014, 9, 1, true));
+        assert_eq!(kyureki(2017, 6, 24), expected(2017, 5, 1, true));
+        assert_eq!(kyureki(2020, 5, 23), expected(2020, 4, 1, true));
+        assert_eq!(kyureki(2023, 3, 22), expected(2023, 2, 1, true));
+        assert_eq!(kyureki(2023, 3, 21), expected(2023, 2, 30, false));
+    }
+
+    #[test]
+    fn kyureki_new_year() {
+        let x = kyureki(2024, 2, 10);
+        assert_eq!(x, expected(2024, 1, 1, false));
+        assert_eq!(x.to_string_jp(), "1月1日");
+        assert_eq!(get_rokuyo(&x), "先勝");
+        assert_eq!(kyureki(2024, 2, 9), expected(2023, 12, 30, false));
+    }
+
+    #[test]
+    fn kyureki_2033() {
+        // 中気を含まない 2033/08/25 の月は閏月にしない  秋分 (2033/09/23) を含む月は9月になる
+        assert_eq!(kyureki(2033, 8, 25), expected(2033, 8, 1, false));
+        assert_eq!(kyureki(2033, 9, 23), expected(2033, 9, 1, false));
+        // 冬至 (2033/12/21) を含む月が11月、次の中気を含まない月が閏11月
+        assert_eq!(kyureki(2033, 11, 22), expected(2033, 11, 1, false));
+        let x = kyureki(2033, 12, 22);
+        assert_eq!(x, expected(2033, 11, 1, true));
+        assert_eq!(x.to_string_jp(), "閏11月1日");
+        assert_eq!(kyureki(2034, 1, 20), expected(2033, 12, 1, false));
+        assert_eq!(kyureki(2034, 2, 19), expected(2034, 1, 1, false));
+        // 春分 (2034/03/20) を含む月は2月
+        assert_eq!(kyureki(2034, 3, 20), expected(2034, 2, 1, false));
+    }
+
+    #[test]
+    fn kanshi_and_sekki() {
+        assert_eq!(get_kanshi_year(2024), "甲辰");
+        assert_eq!(get_eto(2024), "辰");
+        assert_eq!(get_kanshi_day(NaiveDate::from_ymd_opt(2000, 1, 1).unwrap()), "戊午");
+        assert_eq!(get_sekki(NaiveDate::from_ymd_opt(2024, 3, 20).unwrap(), JST), ("春分", true));
+        assert_eq!(get_sekki(NaiveDate::from_ymd_opt(2024, 3, 21).unwrap(), JST), ("春分", false));
+    }
+}
@@ -0,0 +1,1045 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use once_cell::sync::Lazy;
+use anyhow::*;
+use crate::probe;
+
+// 性能値 (CPU, GPU, メモリなど)
+//   MetricSource  : 値を取得する  (Windows の実装は metric_win.rs)
+//   TccMetricRegistry : MetricSource の一覧
+//   TccMetricSampler  : ラベルの format で使われている MetricSource だけを実行する
+// 値は GLOBAL_METRIC_VALUE (平滑化した値), GLOBAL_METRIC_HISTORY (取得した値の履歴) に入れて、
+// get_metric_text で表示用の文字列にする
+
+// placeholder name => value  ("cpu" => 12.3, "gpu0" => 45.0)
+pub static GLOBAL_METRIC_VALUE: Lazy<Mutex<HashMap<String, f64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// placeholder name => text  ("gpu0_name" => "NVIDIA GeForce RTX 4070")
+pub static GLOBAL_METRIC_TEXT: Lazy<Mutex<HashMap<String, String>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// placeholder name => history (raw values, 取得した時刻つき)
+pub static GLOBAL_METRIC_HISTORY: Lazy<Mutex<HashMap<String, TccRingBuffer>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// 履歴の最小の長さ
+const HISTORY_SIZE: usize = 300;
+
+// 古い値から消える固定長の履歴
+#[derive(Debug, Default, Clone)]
+pub struct TccRingBuffer {
+    values: VecDeque<(Instant, f64)>,
+    capacity: usize,
+}
+
+impl TccRingBuffer {
+    pub fn new(capacity: usize) -> TccRingBuffer {
+        TccRingBuffer {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, time: Instant, value: f64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back((time, value));
+    }
+
+    // 新しい方から n 個 (古い順)
+    pub fn get_latest(&self, n: usize) -> Vec<(Instant, f64)> {
+        self.values.iter().skip(self.values.len().saturating_sub(n)).copied().collect()
+    }
+}
+
+// 平滑化  n : 秒数
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TccSmoothing {
+    #[default]
+    None,
+    Average(usize),
+    Ema(usize),
+    Peak(usize),
+}
+
+impl TccSmoothing {
+    // "average", "ema", "peak", "none"
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn parse(s: &str, n: usize) -> Option<TccSmoothing> {
+        let n = n.max(1);
+        match s {
+            "" | "none" => Some(TccSmoothing::None),
+            "average" => Some(TccSmoothing::Average(n)),
+            "ema" => Some(TccSmoothing::Ema(n)),
+            "peak" => Some(TccSmoothing::Peak(n)),
+            _ => None,
+        }
+    }
+
+    // n seconds / interval => samples
+    pub fn get_size(&self, interval: u64) -> usize {
+        match self {
+            TccSmoothing::None => 1,
+            TccSmoothing::Average(n) | TccSmoothing::Ema(n) | TccSmoothing::Peak(n) => n.div_ceil(interval.max(1) as usize).max(1),
+        }
+    }
+
+    // history : 古い順  interval : 取得する間隔 (秒)
+    //   Average : 直近 n 秒 (最新の値の時刻から) の平均
+    //   Ema     : 指数移動平均  α = 2 / (n / interval + 1)、最初の値から計算する
+    //   Peak    : 直近 n 秒の最大
+    // 最新の値が NaN (取れなかった) なら NaN、それ以外は NaN を除いて計算する
+    // 取得が止まっていた間の前の値は n 秒より古いので Average, Peak には使わない
+    pub fn apply(&self, history: &[(Instant, f64)], interval: u64) -> Option<f64> {
+        let &(last_time, last_value) = history.last()?;
+        if last_value.is_nan() {
+            return Some(f64::NAN);
+        }
+        let size = self.get_size(interval);
+        let latest = |n: usize| -> Vec<f64> {
+            let window = Duration::from_secs(n as u64);
+            history.iter().rev().take(size)
+                .take_while(|(time, _)| last_time.saturating_duration_since(*time) < window)
+                .map(|(_, value)| *value)
+                .filter(|x| !x.is_nan())
+                .collect()
+        };
+        match self {
+            TccSmoothing::None => Some(last_value),
+            TccSmoothing::Average(n) => {
+                let values = latest(*n);
+                if values.is_empty() {
+                    None
+                } else {
+                    Some(values.iter().sum::<f64>() / values.len() as f64)
+                }
+            }
+            TccSmoothing::Ema(_) => {
+                let alpha = 2.0 / (size as f64 + 1.0);
+                history.iter().map(|(_, value)| *value).filter(|x| !x.is_nan()).reduce(|ema, x| alpha * x + (1.0 - alpha) * ema)
+            }
+            TccSmoothing::Peak(n) => latest(*n).into_iter().reduce(f64::max),
+        }
+    }
+}
+
+// config.txt > metrics > "cpu": { "interval": 2, "smoothing": "average", "seconds": 10 }
+//   interval : source name, smoothing : placeholder name or source name
+#[derive(Debug, Clone, PartialEq)]
+pub struct TccMetricSetting {
+    // None => source の既定 (MetricSource::default_interval)
+    pub interval: Option<u64>,
+    // n : seconds
+    pub smoothing: TccSmoothing,
+}
+
+impl Default for TccMetricSetting {
+    fn default() -> Self {
+        TccMetricSetting {
+            interval: None,
+            smoothing: TccSmoothing::None,
+        }
+    }
+}
+
+pub trait MetricSource: Send {
+    // placeholder name (without "_", "0", "-")  "cpu", "gpu0", ...
+    fn provides(&self, name: &str) -> bool;
+
+    // placeholder names used by labels (only names this source provides)  called before init
+    fn set_names(&mut self, _names: &[String]) {}
+
+    // seconds  (config.txt > metrics > interval で変えられる)
+    fn default_interval(&self) -> u64 {
+        1
+    }
+
+    // called once before the first sample (only when referenced)
+    fn init(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    // (placeholder name, value)  値が取れないときは NaN
+    fn sample(&mut self) -> Vec<(String, f64)>;
+
+    // (placeholder name, text)  called after sample
+    fn get_texts(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    fn close(&mut self) {}
+}
+
+// "_cpu" => ("_", "cpu"), "0gpu1" => ("0", "gpu1"), "cpu" => ("", "cpu")
+pub fn split_prefix(name: &str) -> (&str, &str) {
+    match name.chars().next() {
+        Some('_' | '0' | '-') if name.len() > 1 => name.split_at(1),
+        _ => ("", name),
+    }
+}
+
+// 値の種類  (placeholder name で決まる)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TccMetricKind {
+    Percent,
+    // bytes/s
+    Rate,
+    // bytes
+    Size,
+    // ms
+    Latency,
+}
+
+// "disk_read:C" => Rate, "disk:C:free", "mem_used", "gpu0_dedicated", "proc:code.exe:mem" => Size, "disk:C:used%", "mem" => Percent
+// "ping:gateway", "tcp:build-server:443" => Latency  ("ping:gateway:loss", "ping:gateway:ok" => Percent)
+pub fn get_metric_kind(name: &str) -> TccMetricKind {
+    match name.split(':').next().unwrap_or("") {
+        "net_down" | "net_up" | "disk_read" | "disk_write" => TccMetricKind::Rate,
+        "ping" | "tcp" if probe::parse_probe_name(name).is_some_and(|x| x.1 == probe::TccProbeField::Latency) => TccMetricKind::Latency,
+        _ if name.ends_with('%') => TccMetricKind::Percent,
+        x if x == "disk" || x.starts_with("mem_") || x.ends_with("_dedicated") || x.ends_with("_shared") => TccMetricKind::Size,
+        "proc" if name.ends_with(":mem") => TccMetricKind::Size,
+        _ => TccMetricKind::Percent,
+    }
+}
+
+// 表示の単位  config.txt > network > unit, size_unit, missing_value
+#[derive(Debug, Clone, PartialEq)]
+pub struct TccMetricUnit {
+    // bps, Kbps, Mbps  (false => B/s, KB/s, MB/s)
+    pub bits: bool,
+    // KB, MB, GB (1000)  (false => KiB, MiB, GiB (1024))
+    pub decimal: bool,
+    // 値が取れないとき (NaN) の表示
+    pub missing: String,
+}
+
+impl Default for TccMetricUnit {
+    fn default() -> Self {
+        TccMetricUnit {
+            bits: false,
+            decimal: false,
+            missing: "--".to_string(),
+        }
+    }
+}
+
+// 1000 以上なら単位を上げる  (数字, 単位)
+//   整数、単位を上げて 10 未満なら小数1桁  単位は丸める前の値で決める (999.6 B/s => 1000 B/s)
+pub fn format_scaled(value: f64, base: f64, units: &[&'static str]) -> (String, &'static str) {
+    let mut value = value.max(0.0);
+    let mut i = 0;
+    while i + 1 < units.len() && value >= 1000.0 {
+        value /= base;
+        i += 1;
+    }
+    let number = if i > 0 && (value * 10.0).round() < 100.0 {
+        format!("{value:.1}")
+    } else {
+        format!("{value:.0}")
+    };
+    (number, units[i])
+}
+
+// 12345.0 => ("12", "KB/s"), bits => ("99", "Kbps")
+pub fn format_rate(bytes_per_second: f64, unit: &TccMetricUnit) -> (String, &'static str) {
+    if unit.bits {
+        format_scaled(bytes_per_second * 8.0, 1000.0, &["bps", "Kbps", "Mbps", "Gbps"])
+    } else {
+        format_scaled(bytes_per_second, 1000.0, &["B/s", "KB/s", "MB/s", "GB/s"])
+    }
+}
+
+// 123456789012.0 => ("115", "GiB"), decimal => ("123", "GB")
+pub fn format_size(bytes: f64, unit: &TccMetricUnit) -> (String, &'static str) {
+    if unit.decimal {
+        format_scaled(bytes, 1000.0, &["B", "KB", "MB", "GB", "TB"])
+    } else {
+        format_scaled(bytes, 1024.0, &["B", "KiB", "MiB", "GiB", "TiB"])
+    }
+}
+
+// raw value  (smoothing 後)
+pub fn get_metric_value(name: &str) -> Option<f64> {
+    GLOBAL_METRIC_VALUE.lock().unwrap().get(name).copied()
+}
+
+// cpu, _cpu : スペース詰め3桁, 0cpu : 0詰め3桁, -cpu : 1～3桁
+// net_down, _net_down, 0net_down : " 1.2 MB/s" (数字はスペース詰め4桁), -net_down : 1234567 (bytes/s or bps)
+// disk:C:free : " 120 GiB", -disk:C:free : 128849018880 (bytes)
+// ping:gateway : "  12 ms", -ping:gateway : 12
+// gpu0_name : text (prefix なし)
+pub fn get_metric_text(name: &str, unit: &TccMetricUnit) -> Option<String> {
+    let (prefix, base) = split_prefix(name);
+    if let Some(x) = GLOBAL_METRIC_TEXT.lock().unwrap().get(base) {
+        return Some(x.to_string());
+    }
+    let value = GLOBAL_METRIC_VALUE.lock().unwrap().get(base).copied()?;
+    if value.is_nan() {
+        return Some(unit.missing.to_string());
+    }
+    let kind = get_metric_kind(base);
+    if kind != TccMetricKind::Percent {
+        if prefix == "-" {
+            let value = if kind == TccMetricKind::Rate && unit.bits { value * 8.0 } else { value };
+            return Some((value.round() as i64).to_string());
+        }
+        let (number, unit_name) = match kind {
+            TccMetricKind::Rate => format_rate(value, unit),
+            TccMetricKind::Latency => ((value.round() as i64).to_string(), "ms"),
+            _ => format_size(value, unit),
+        };
+        return Some(format!("{number:>4} {unit_name}"));
+    }
+    let value = value.round() as i64;
+    Some(match prefix {
+        "0" => format!("{value:0>3}"),
+        "-" => value.to_string(),
+        _ => format!("{value:>3}"),
+    })
+}
+
+// Processor Information instance "group,number"  ("0,3" => (0, 3), "_Total", "0,_Total" => None)
+pub fn parse_processor_instance(name: &str) -> Option<(u32, u32)> {
+    let (group, number) = name.split_once(',')?;
+    Some((group.trim().parse().ok()?, number.trim().parse().ok()?))
+}
+
+// per-core values => cpu0, cpu1, ... (processor group order), cpu_max, cpu_count
+pub fn get_core_values(instances: &[(String, f64)]) -> Vec<(String, f64)> {
+    let mut cores: Vec<((u32, u32), f64)> = instances
+        .iter()
+        .filter_map(|(name, value)| parse_processor_instance(name).map(|x| (x, *value)))
+        .collect();
+    cores.sort_by_key(|x| x.0);
+    let mut values: Vec<(String, f64)> = cores
+        .iter()
+        .enumerate()
+        .map(|(i, (_, value))| (format!("cpu{i}"), value.min(100.0).round()))
+        .collect();
+    if let Some(x) = cores.iter().map(|x| x.1.min(100.0).round()).reduce(f64::max) {
+        values.push(("cpu_max".to_string(), x));
+    }
+    values.push(("cpu_count".to_string(), cores.len() as f64));
+    values
+}
+
+// "C:" => disk:C:total, disk:C:used, disk:C:free (bytes), disk:C:used%, disk:C:free%
+pub fn get_disk_space_values(drive: &str, total: u64, free: u64) -> Vec<(String, f64)> {
+    let letter = drive.trim_end_matches(['\\', ':']);
+    let used = total.saturating_sub(free);
+    let mut values = vec![
+        (format!("disk:{letter}:total"), total as f64),
+        (format!("disk:{letter}:used"), used as f64),
+        (format!("disk:{letter}:free"), free as f64),
+    ];
+    if total > 0 {
+        values.push((format!("disk:{letter}:used%"), (used as f64 / total as f64 * 100.0).round()));
+        values.push((format!("disk:{letter}:free%"), (free as f64 / total as f64 * 100.0).round()));
+    }
+    values
+}
+
+// GlobalMemoryStatusEx (bytes)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TccMemoryStatus {
+    pub total: u64,
+    pub available: u64,
+    pub commit_limit: u64,
+    pub commit_available: u64,
+    // \Paging File(_Total)\% Usage
+    pub pagefile_usage: Option<f64>,
+}
+
+// mem, mem_used, mem_available, mem_total, mem_commit, mem_commit_limit, mem_commit%,
+// mem_pagefile, mem_pagefile_total, mem_pagefile%
+pub fn get_memory_values(status: &TccMemoryStatus) -> Vec<(String, f64)> {
+    let percent = |x: u64, total: u64| if total == 0 { 0.0 } else { (x as f64 / total as f64 * 100.0).round() };
+    let used = status.total.saturating_sub(status.available);
+    let commit = status.commit_limit.saturating_sub(status.commit_available);
+    let mut values = vec![
+        ("mem".to_string(), percent(used, status.total)),
+        ("mem_used".to_string(), used as f64),
+        ("mem_available".to_string(), status.available as f64),
+        ("mem_total".to_string(), status.total as f64),
+        ("mem_commit".to_string(), commit as f64),
+        ("mem_commit_limit".to_string(), status.commit_limit as f64),
+        ("mem_commit%".to_string(), percent(commit, status.commit_limit)),
+    ];
+    // commit limit = 物理メモリ + ページファイル
+    let pagefile_total = status.commit_limit.saturating_sub(status.total);
+    values.push(("mem_pagefile_total".to_string(), pagefile_total as f64));
+    if let Some(x) = status.pagefile_usage {
+        values.push(("mem_pagefile".to_string(), (pagefile_total as f64 * x / 100.0).round()));
+        values.push(("mem_pagefile%".to_string(), x.round()));
+    }
+    values
+}
+
+// GetSystemPowerStatus
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TccPowerStatus {
+    pub ac_line: u8,  // 0 : offline, 1 : online, 255 : unknown
+    pub battery_flag: u8,  // 8 : charging, 128 : no battery, 255 : unknown
+    pub battery_percent: u8,  // 255 : unknown
+    pub battery_seconds: u32,  // u32::MAX : unknown
+}
+
+const BATTERY_FLAG_CHARGING: u8 = 8;
+const BATTERY_FLAG_NO_BATTERY: u8 = 128;
+const POWER_STATUS_UNKNOWN: u8 = 255;
+
+fn has_battery(status: &TccPowerStatus) -> bool {
+    status.battery_flag != POWER_STATUS_UNKNOWN
+        && status.battery_flag & BATTERY_FLAG_NO_BATTERY == 0
+        && status.battery_percent != POWER_STATUS_UNKNOWN
+}
+
+// "charging", "discharging", "full", "not_charging"  (バッテリーがない => "")
+//   not_charging : 電源につながっているが 100% でなく充電していない (充電の上限を設定しているときなど)
+pub fn get_battery_state(status: &TccPowerStatus) -> &'static str {
+    if !has_battery(status) {
+        ""
+    } else if status.battery_flag & BATTERY_FLAG_CHARGING != 0 {
+        "charging"
+    } else if status.ac_line != 1 {
+        "discharging"
+    } else if status.battery_percent >= 100 {
+        "full"
+    } else {
+        "not_charging"
+    }
+}
+
+// battery (%), battery_time (seconds, 放電中だけ。充電中・計算中は NaN)
+// バッテリーがない => なし
+pub fn get_power_values(status: &TccPowerStatus) -> Vec<(String, f64)> {
+    if !has_battery(status) {
+        return Vec::new();
+    }
+    let seconds = if get_battery_state(status) == "discharging" && status.battery_seconds != u32::MAX {
+        status.battery_seconds as f64
+    } else {
+        f64::NAN
+    };
+    vec![
+        ("battery".to_string(), status.battery_percent.min(100) as f64),
+        ("battery_time".to_string(), seconds),
+    ]
+}
+
+// ac ("1", "0", 不明 => ""), battery_state
+pub fn get_power_texts(status: &TccPowerStatus) -> Vec<(String, String)> {
+    let ac = match status.ac_line {
+        0 => "0",
+        1 => "1",
+        _ => "",
+    };
+    vec![
+        ("ac".to_string(), ac.to_string()),
+        ("battery_state".to_string(), get_battery_state(status).to_string()),
+    ]
+}
+
+// disk I/O counter instance => placeholder suffix
+//   PhysicalDisk "_Total" => "", "0 C: D:" => ":0"
+//   LogicalDisk "C:" => ":C", "_Total", "HarddiskVolume1" => None (ドライブ文字がない)
+pub fn get_disk_io_suffix(instance: &str, is_physical: bool) -> Option<String> {
+    if is_physical {
+        if instance == "_Total" {
+            return Some("".to_string());
+        }
+        let number = instance.split_whitespace().next()?;
+        number.chars().all(|x| x.is_ascii_digit()).then(|| format!(":{number}"))
+    } else {
+        let letter = instance.strip_suffix(':')?;
+        (letter.len() == 1 && letter.chars().all(|x| x.is_ascii_alphabetic())).then(|| format!(":{}", letter.to_ascii_uppercase()))
+    }
+}
+
+// source name => source  (同じ名前で登録すると置き換わる)
+#[derive(Default)]
+pub struct TccMetricRegistry {
+    sources: HashMap<String, Box<dyn MetricSource>>,
+}
+
+impl TccMetricRegistry {
+    pub fn register(&mut self, name: &str, source: Box<dyn MetricSource>) {
+        self.sources.insert(name.to_string(), source);
+    }
+
+    // placeholder names used by labels => sampler  (init は TccMetricSampler::init)
+    pub fn build(self, placeholder_names: &[String], settings: &HashMap<String, TccMetricSetting>) -> TccMetricSampler {
+        let mut sampler = TccMetricSampler {
+            settings: settings.clone(),
+            ..Default::default()
+        };
+        let mut sources: Vec<(String, Box<dyn MetricSource>)> = self.sources.into_iter().collect();
+        sources.sort_by(|a, b| a.0.cmp(&b.0));
+        for (source_name, mut source) in sources {
+            let mut names: Vec<String> = placeholder_names
+                .iter()
+                .map(|x| split_prefix(x).1)
+                .filter(|x| source.provides(x))
+                .map(|x| x.to_string())
+                .collect();
+            if names.is_empty() {
+                continue;
+            }
+            names.sort();
+            names.dedup();
+            source.set_names(&names);
+            let source_setting = settings.get(&source_name).cloned().unwrap_or_default();
+            let interval = source_setting.interval.unwrap_or(source.default_interval());
+            sampler.slots.push(TccMetricSlot {
+                source_name,
+                source,
+                interval: Duration::from_secs(interval.max(1)),
+                last_sampled: None,
+            });
+        }
+        sampler
+    }
+}
+
+struct TccMetricSlot {
+    source_name: String,
+    source: Box<dyn MetricSource>,
+    interval: Duration,
+    last_sampled: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct TccMetricSampler {
+    slots: Vec<TccMetricSlot>,
+    settings: HashMap<String, TccMetricSetting>,
+}
+
+impl TccMetricSampler {
+    // 最初の sample の前に、sample と同じスレッドで呼ぶ (WMI の COM 初期化など)
+    // 初期化できなかった source は close して取り除いて、最初のエラーを返す
+    pub fn init(&mut self) -> anyhow::Result<()> {
+        let mut error: Option<anyhow::Error> = None;
+        self.slots.retain_mut(|slot| match slot.source.init() {
+            Err(err) => {
+                slot.source.close();
+                error.get_or_insert(anyhow!(format!("metric source {} : {err}", slot.source_name)));
+                false
+            }
+            std::result::Result::Ok(_) => true,
+        });
+        match error {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    // interval が来た source だけ取得する
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn sample(&mut self) {
+        self.sample_at(Instant::now());
+    }
+
+    pub fn sample_at(&mut self, now: Instant) {
+        let mut values: Vec<(String, f64, TccSmoothing, u64)> = Vec::new();
+        let mut texts: Vec<(String, String)> = Vec::new();
+        for slot in self.slots.iter_mut() {
+            // 50ms : timer jitter
+            if slot.last_sampled.is_some_and(|x| now.saturating_duration_since(x) + Duration::from_millis(50) < slot.interval) {
+                continue;
+            }
+            slot.last_sampled = Some(now);
+            let interval = slot.interval.as_secs();
+            let source_smoothing = self.settings.get(&slot.source_name).map(|x| x.smoothing);
+            for (name, value) in slot.source.sample() {
+                let smoothing = self.settings.get(&name).map(|x| x.smoothing).or(source_smoothing).unwrap_or_default();
+                values.push((name, value, smoothing, interval));
+            }
+            texts.extend(slot.source.get_texts());
+        }
+        GLOBAL_METRIC_TEXT.lock().unwrap().extend(texts);
+        let mut global_metric_history_hm = GLOBAL_METRIC_HISTORY.lock().unwrap();
+        let mut global_metric_value_hm = GLOBAL_METRIC_VALUE.lock().unwrap();
+        for (name, value, smoothing, interval) in values {
+            let history = global_metric_history_hm
+                .entry(name.clone())
+                .or_insert_with(|| TccRingBuffer::new(HISTORY_SIZE.max(smoothing.get_size(interval))));
+            history.push(now, value);
+            // EMA uses all history
+            let n = if matches!(smoothing, TccSmoothing::Ema(_)) { usize::MAX } else { smoothing.get_size(interval) };
+            if let Some(x) = smoothing.apply(&history.get_latest(n), interval) {
+                global_metric_value_hm.insert(name, x);
+            }
+        }
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn close(&mut self) {
+        for slot in self.slots.iter_mut() {
+            slot.source.close();
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+    // 固定の値 (1回ごとに次の値) を返す
+    #[derive(Debug, Default, Clone)]
+    struct TccFakeMetricSource {
+        names: Vec<String>,
+        values: Vec<f64>,
+        index: usize,
+        texts: Vec<(String, String)>,
+        fail_init: bool,
+        closed: Arc<AtomicBool>,
+    }
+
+    impl TccFakeMetricSource {
+        fn new(names: &[&str], values: &[f64]) -> TccFakeMetricSource {
+            TccFakeMetricSource {
+                names: names.iter().map(|x| x.to_string()).collect(),
+                values: values.to_vec(),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl MetricSource for TccFakeMetricSource {
+        fn provides(&self, name: &str) -> bool {
+            self.names.iter().any(|x| x == name) || self.texts.iter().any(|x| x.0 == name)
+        }
+
+        fn init(&mut self) -> anyhow::Result<()> {
+            if self.fail_init {
+                return Err(anyhow!("fake init error"));
+            }
+            Ok(())
+        }
+
+        fn sample(&mut self) -> Vec<(String, f64)> {
+            let value = self.values[self.index % self.values.len()];
+            self.index += 1;
+            self.names.iter().map(|x| (x.to_string(), value)).collect()
+        }
+
+        fn get_texts(&self) -> Vec<(String, String)> {
+            self.texts.clone()
+        }
+
+        fn close(&mut self) {
+            self.closed.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| x.to_string()).collect()
+    }
+
+    fn text(name: &str) -> Option<String> {
+        get_metric_text(name, &TccMetricUnit::default())
+    }
+
+    #[test]
+    fn pipeline_formats_percent_with_prefixes() {
+        let mut registry = TccMetricRegistry::default();
+        registry.register("fake", Box::new(TccFakeMetricSource::new(&["test_pipeline_cpu"], &[7.4])));
+        let mut sampler = registry.build(&names(&["_test_pipeline_cpu", "0test_pipeline_cpu"]), &HashMap::new());
+        sampler.init().unwrap();
+        sampler.sample_at(Instant::now());
+        assert_eq!(text("test_pipeline_cpu").as_deref(), Some("  7"));
+        assert_eq!(text("_test_pipeline_cpu").as_deref(), Some("  7"));
+        assert_eq!(text("0test_pipeline_cpu").as_deref(), Some("007"));
+        assert_eq!(text("-test_pipeline_cpu").as_deref(), Some("7"));
+    }
+
+    #[test]
+    fn pipeline_formats_rate_size_text_and_missing() {
+        let mut registry = TccMetricRegistry::default();
+        registry.register("rate", Box::new(TccFakeMetricSource::new(&["net_down"], &[1_234_567.0])));
+        registry.register("size", Box::new(TccFakeMetricSource::new(&["mem_test_pipeline"], &[3.5 * 1024.0 * 1024.0 * 1024.0])));
+        registry.register("missing", Box::new(TccFakeMetricSource::new(&["test_pipeline_temp"], &[f64::NAN])));
+        let mut source = TccFakeMetricSource::new(&[], &[0.0]);
+        source.texts = vec![("test_pipeline_name".to_string(), "NVIDIA GeForce RTX 4070".to_string())];
+        registry.register("text", Box::new(source));
+        let mut sampler = registry.build(&names(&["net_down", "-mem_test_pipeline", "test_pipeline_temp", "test_pipeline_name"]), &HashMap::new());
+        sampler.init().unwrap();
+        sampler.sample_at(Instant::now());
+        assert_eq!(text("net_down").as_deref(), Some(" 1.2 MB/s"));
+        assert_eq!(text("mem_test_pipeline").as_deref(), Some(" 3.5 GiB"));
+        assert_eq!(text("-mem_test_pipeline").as_deref(), Some("3758096384"));
+        assert_eq!(text("test_pipeline_temp").as_deref(), Some("--"));
+        assert_eq!(text("test_pipeline_name").as_deref(), Some("NVIDIA GeForce RTX 4070"));
+        let unit = TccMetricUnit { bits: true, missing: "n/a".to_string(), ..Default::default() };
+        assert_eq!(get_metric_text("net_down", &unit).as_deref(), Some(" 9.9 Mbps"));
+        assert_eq!(get_metric_text("-net_down", &unit).as_deref(), Some("9876536"));
+        assert_eq!(get_metric_text("test_pipeline_temp", &unit).as_deref(), Some("n/a"));
+        assert_eq!(text("test_pipeline_unknown"), None);
+    }
+
+    #[test]
+    fn pipeline_formats_probe_latency() {
+        let mut registry = TccMetricRegistry::default();
+        registry.register("ping", Box::new(TccFakeMetricSource::new(&["ping:test-latency.example"], &[12.4, 1234.5, f64::NAN])));
+        registry.register("tcp", Box::new(TccFakeMetricSource::new(&["tcp:test-latency.example:443:loss"], &[10.0])));
+        let mut sampler = registry.build(&names(&["ping:test-latency.example", "tcp:test-latency.example:443:loss"]), &HashMap::new());
+        sampler.init().unwrap();
+        let start = Instant::now();
+        sampler.sample_at(start);
+        assert_eq!(text("ping:test-latency.example").as_deref(), Some("  12 ms"));
+        assert_eq!(text("-ping:test-latency.example").as_deref(), Some("12"));
+        assert_eq!(text("tcp:test-latency.example:443:loss").as_deref(), Some(" 10"));
+        sampler.sample_at(start + Duration::from_secs(1));
+        assert_eq!(text("ping:test-latency.example").as_deref(), Some("1235 ms"));
+        sampler.sample_at(start + Duration::from_secs(2));
+        assert_eq!(text("ping:test-latency.example").as_deref(), Some("--"));
+        assert_eq!(get_metric_kind("tcp:build-server:443"), TccMetricKind::Latency);
+        assert_eq!(get_metric_kind("ping:gateway:ok"), TccMetricKind::Percent);
+        assert_eq!(get_metric_kind("ping:[::1]"), TccMetricKind::Latency);
+    }
+
+    #[test]
+    fn pipeline_samples_only_referenced_sources() {
+        let mut registry = TccMetricRegistry::default();
+        registry.register("used", Box::new(TccFakeMetricSource::new(&["test_referenced_used"], &[1.0])));
+        registry.register("unused", Box::new(TccFakeMetricSource::new(&["test_referenced_unused"], &[2.0])));
+        let mut sampler = registry.build(&names(&["test_referenced_used"]), &HashMap::new());
+        sampler.init().unwrap();
+        sampler.sample_at(Instant::now());
+        assert_eq!(get_metric_value("test_referenced_used"), Some(1.0));
+        assert_eq!(get_metric_value("test_referenced_unused"), None);
+    }
+
+    #[test]
+    fn pipeline_drops_sources_that_fail_init() {
+        let mut registry = TccMetricRegistry::default();
+        let mut source = TccFakeMetricSource::new(&["test_init_failed"], &[1.0]);
+        source.fail_init = true;
+        let closed = source.closed.clone();
+        registry.register("failed", Box::new(source));
+        registry.register("ok", Box::new(TccFakeMetricSource::new(&["test_init_ok"], &[2.0])));
+        let mut sampler = registry.build(&names(&["test_init_failed", "test_init_ok"]), &HashMap::new());
+        let err = sampler.init().unwrap_err();
+        assert!(err.to_string().contains("metric source failed"));
+        assert!(closed.load(Ordering::Relaxed));
+        sampler.sample_at(Instant::now());
+        assert_eq!(get_metric_value("test_init_failed"), None);
+        assert_eq!(get_metric_value("test_init_ok"), Some(2.0));
+    }
+
+    #[test]
+    fn pipeline_respects_interval_and_smoothing() {
+        let mut registry = TccMetricRegistry::default();
+        registry.register("slow", Box::new(TccFakeMetricSource::new(&["test_interval"], &[10.0, 20.0, 30.0])));
+        let mut settings: HashMap<String, TccMetricSetting> = HashMap::new();
+        settings.insert("slow".to_string(), TccMetricSetting { interval: Some(2), smoothing: TccSmoothing::Average(4) });
+        let mut sampler = registry.build(&names(&["test_interval"]), &settings);
+        sampler.init().unwrap();
+        let start = Instant::now();
+        sampler.sample_at(start);
+        assert_eq!(get_metric_value("test_interval"), Some(10.0));
+        // interval (2s) が来ていない
+        sampler.sample_at(start + Duration::from_secs(1));
+        assert_eq!(get_metric_value("test_interval"), Some(10.0));
+        // average 4s / interval 2s => 2 samples
+        sampler.sample_at(start + Duration::from_secs(2));
+        assert_eq!(get_metric_value("test_interval"), Some(15.0));
+        sampler.sample_at(start + Duration::from_secs(4));
+        assert_eq!(get_metric_value("test_interval"), Some(25.0));
+    }
+
+    // (秒, 値) => 履歴
+    fn series(start: Instant, values: &[(u64, f64)]) -> Vec<(Instant, f64)> {
+        values.iter().map(|(x, y)| (start + Duration::from_secs(*x), *y)).collect()
+    }
+
+    #[test]
+    fn smoothing_average_and_peak_use_the_time_window() {
+        let start = Instant::now();
+        let history = series(start, &[(0, 10.0), (1, 20.0), (2, 30.0), (3, 40.0)]);
+        assert_eq!(TccSmoothing::None.apply(&history, 1), Some(40.0));
+        assert_eq!(TccSmoothing::Average(2).apply(&history, 1), Some(35.0));
+        assert_eq!(TccSmoothing::Average(10).apply(&history, 1), Some(25.0));
+        assert_eq!(TccSmoothing::Peak(3).apply(&history[..2], 1), Some(20.0));
+        assert_eq!(TccSmoothing::Average(3).apply(&[], 1), None);
+
+        // 取得が止まっていた (100秒の空白) 前の値は使わない
+        let history = series(start, &[(0, 90.0), (1, 80.0), (100, 10.0), (101, 20.0)]);
+        assert_eq!(TccSmoothing::Average(5).apply(&history, 1), Some(15.0));
+        assert_eq!(TccSmoothing::Peak(5).apply(&history, 1), Some(20.0));
+        assert_eq!(TccSmoothing::Average(5).apply(&history[..3], 1), Some(10.0));
+    }
+
+    #[test]
+    fn smoothing_skips_missing_values_inside_the_window() {
+        let start = Instant::now();
+        let nan = f64::NAN;
+        // NaN を除いても窓の外の値には広げない
+        let history = series(start, &[(0, 100.0), (1, 50.0), (2, nan), (3, nan), (4, 10.0)]);
+        assert_eq!(TccSmoothing::Average(3).apply(&history, 1), Some(10.0));
+        assert_eq!(TccSmoothing::Average(4).apply(&history, 1), Some(30.0));
+        assert_eq!(TccSmoothing::Peak(3).apply(&history, 1), Some(10.0));
+        // 最新の値が取れなかったら NaN
+        assert!(TccSmoothing::Average(3).apply(&history[..3], 1).unwrap().is_nan());
+        // interval 2秒なら 4秒 => 2個
+        let history = series(start, &[(0, 10.0), (2, 20.0), (4, 30.0)]);
+        assert_eq!(TccSmoothing::Average(4).apply(&history, 2), Some(25.0));
+        assert_eq!(TccSmoothing::Average(5).apply(&history, 2), Some(20.0));
+    }
+
+    #[test]
+    fn smoothing_ema() {
+        let start = Instant::now();
+        let history = series(start, &[(0, 10.0), (1, f64::NAN), (2, 40.0)]);
+        // α = 2 / (3 + 1)
+        assert_eq!(TccSmoothing::Ema(3).apply(&history, 1), Some(25.0));
+        assert_eq!(TccSmoothing::Ema(6).apply(&history, 2), Some(25.0));
+        assert_eq!(TccSmoothing::Ema(3).get_size(1), 3);
+        assert_eq!(TccSmoothing::None.get_size(5), 1);
+    }
+
+    #[test]
+    fn ring_buffer_keeps_the_latest_values() {
+        let start = Instant::now();
+        let mut buffer = TccRingBuffer::new(3);
+        for (i, x) in [1.0, 2.0, 3.0, 4.0].into_iter().enumerate() {
+            buffer.push(start + Duration::from_secs(i as u64), x);
+        }
+        let values: Vec<f64> = buffer.get_latest(usize::MAX).into_iter().map(|x| x.1).collect();
+        assert_eq!(values, vec![2.0, 3.0, 4.0]);
+        assert_eq!(buffer.get_latest(1), vec![(start + Duration::from_secs(3), 4.0)]);
+    }
+
+    fn instances(values: &[(&str, f64)]) -> Vec<(String, f64)> {
+        values.iter().map(|(x, y)| (x.to_string(), *y)).collect()
+    }
+
+    #[test]
+    fn processor_instance_names() {
+        assert_eq!(parse_processor_instance("0,3"), Some((0, 3)));
+        assert_eq!(parse_processor_instance("1, 12"), Some((1, 12)));
+        assert_eq!(parse_processor_instance("_Total"), None);
+        assert_eq!(parse_processor_instance("0,_Total"), None);
+        assert_eq!(parse_processor_instance("3"), None);
+        assert_eq!(parse_processor_instance(""), None);
+    }
+
+    #[test]
+    fn core_values_follow_processor_group_order() {
+        let values = get_core_values(&instances(&[
+            ("_Total", 50.0),
+            ("0,_Total", 50.0),
+            ("1,0", 70.0),
+            ("0,10", 20.4),
+            ("0,9", 100.7),
+            ("0,0", 5.5),
+        ]));
+        assert_eq!(values, instances(&[
+            ("cpu0", 6.0),
+            ("cpu1", 100.0),
+            ("cpu2", 20.0),
+            ("cpu3", 70.0),
+            ("cpu_max", 100.0),
+            ("cpu_count", 4.0),
+        ]));
+        assert_eq!(get_core_values(&instances(&[("_Total", 1.0)])), instances(&[("cpu_count", 0.0)]));
+    }
+
+    fn rate(x: f64, bits: bool) -> String {
+        let (number, unit) = format_rate(x, &TccMetricUnit { bits, ..Default::default() });
+        format!("{number} {unit}")
+    }
+
+    fn size(x: f64, decimal: bool) -> String {
+        let (number, unit) = format_size(x, &TccMetricUnit { decimal, ..Default::default() });
+        format!("{number} {unit}")
+    }
+
+    #[test]
+    fn format_rate_boundaries() {
+        assert_eq!(rate(0.0, false), "0 B/s");
+        assert_eq!(rate(-5.0, false), "0 B/s");
+        assert_eq!(rate(999.0, false), "999 B/s");
+        // 単位は丸める前の値で決める
+        assert_eq!(rate(999.6, false), "1000 B/s");
+        assert_eq!(rate(1000.0, false), "1.0 KB/s");
+        assert_eq!(rate(9949.0, false), "9.9 KB/s");
+        assert_eq!(rate(9950.0, false), "10 KB/s");
+        assert_eq!(rate(12345.0, false), "12 KB/s");
+        assert_eq!(rate(999_999.0, false), "1000 KB/s");
+        assert_eq!(rate(1_000_000.0, false), "1.0 MB/s");
+        assert_eq!(rate(5.0e12, false), "5000 GB/s");
+        assert_eq!(rate(12345.0, true), "99 Kbps");
+        assert_eq!(rate(124.9, true), "999 bps");
+        assert_eq!(rate(125.0, true), "1.0 Kbps");
+    }
+
+    #[test]
+    fn format_size_boundaries() {
+        // 1024 進でも 3桁に収まるように 1000 で単位を上げる
+        assert_eq!(size(999.0, false), "999 B");
+        assert_eq!(size(1000.0, false), "1.0 KiB");
+        assert_eq!(size(10188.0, false), "9.9 KiB");
+        assert_eq!(size(999.0 * 1024.0, false), "999 KiB");
+        assert_eq!(size(1000.0 * 1024.0, false), "1.0 MiB");
+        assert_eq!(size(123456789012.0, false), "115 GiB");
+        assert_eq!(size(123456789012.0, true), "123 GB");
+        assert_eq!(size(999.9, true), "1000 B");
+        assert_eq!(size(1000.0, true), "1.0 KB");
+        assert_eq!(size(2.5e15, true), "2500 TB");
+    }
+
+    #[test]
+    fn disk_io_suffix_for_physical_disks() {
+        assert_eq!(get_disk_io_suffix("_Total", true), Some("".to_string()));
+        assert_eq!(get_disk_io_suffix("0 C: D:", true), Some(":0".to_string()));
+        assert_eq!(get_disk_io_suffix("12", true), Some(":12".to_string()));
+        assert_eq!(get_disk_io_suffix("", true), None);
+        assert_eq!(get_disk_io_suffix("C:", true), None);
+    }
+
+    #[test]
+    fn disk_io_suffix_for_drive_letters() {
+        assert_eq!(get_disk_io_suffix("C:", false), Some(":C".to_string()));
+        assert_eq!(get_disk_io_suffix("d:", false), Some(":D".to_string()));
+        assert_eq!(get_disk_io_suffix("_Total", false), None);
+        assert_eq!(get_disk_io_suffix("HarddiskVolume1", false), None);
+        assert_eq!(get_disk_io_suffix("CD:", false), None);
+        assert_eq!(get_disk_io_suffix("1:", false), None);
+    }
+
+    #[test]
+    fn disk_space_values() {
+        let gib = 1024.0 * 1024.0 * 1024.0;
+        let values = get_disk_space_values("C:\\", 400 * (1 << 30), 100 * (1 << 30));
+        assert_eq!(values, instances(&[
+            ("disk:C:total", 400.0 * gib),
+            ("disk:C:used", 300.0 * gib),
+            ("disk:C:free", 100.0 * gib),
+            ("disk:C:used%", 75.0),
+            ("disk:C:free%", 25.0),
+        ]));
+        assert_eq!(get_disk_space_values("D:", 3, 2)[0].0, "disk:D:total");
+        // 空き > 合計 (クォータ) でも used は 0
+        assert_eq!(get_disk_space_values("E", 100, 200)[1], ("disk:E:used".to_string(), 0.0));
+        // 合計 0 (メディアなし) は割合を出さない
+        assert_eq!(get_disk_space_values("F:", 0, 0).len(), 3);
+    }
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn memory_value(values: &[(String, f64)], name: &str) -> Option<f64> {
+        values.iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+
+    #[test]
+    fn memory_values() {
+        // 物理 16 GiB (空き 4 GiB), コミット上限 20 GiB (空き 6 GiB), ページファイル 25%
+        let status = TccMemoryStatus {
+            total: 16 * GIB,
+            available: 4 * GIB,
+            commit_limit: 20 * GIB,
+            commit_available: 6 * GIB,
+            pagefile_usage: Some(25.4),
+        };
+        let values = get_memory_values(&status);
+        for (name, expected) in [
+            ("mem", 75.0),
+            ("mem_used", (12 * GIB) as f64),
+            ("mem_available", (4 * GIB) as f64),
+            ("mem_total", (16 * GIB) as f64),
+            ("mem_commit", (14 * GIB) as f64),
+            ("mem_commit_limit", (20 * GIB) as f64),
+            ("mem_commit%", 70.0),
+            ("mem_pagefile_total", (4 * GIB) as f64),
+            ("mem_pagefile", (4.0 * GIB as f64 * 0.254).round()),
+            ("mem_pagefile%", 25.0),
+        ] {
+            assert_eq!(memory_value(&values, name), Some(expected), "{name}");
+        }
+    }
+
+    #[test]
+    fn memory_values_without_pagefile_counter() {
+        let status = TccMemoryStatus {
+            total: 8 * GIB,
+            available: 8 * GIB,
+            commit_limit: 8 * GIB,
+            commit_available: 5 * GIB,
+            pagefile_usage: None,
+        };
+        let values = get_memory_values(&status);
+        assert_eq!(memory_value(&values, "mem"), Some(0.0));
+        assert_eq!(memory_value(&values, "mem_pagefile_total"), Some(0.0));
+        assert_eq!(memory_value(&values, "mem_pagefile"), None);
+        assert_eq!(memory_value(&values, "mem_pagefile%"), None);
+    }
+
+    #[test]
+    fn memory_values_with_zero_total() {
+        let values = get_memory_values(&TccMemoryStatus { pagefile_usage: Some(50.0), ..Default::default() });
+        assert!(values.iter().all(|x| x.1.is_finite()), "{values:?}");
+        assert_eq!(memory_value(&values, "mem"), Some(0.0));
+        assert_eq!(memory_value(&values, "mem_commit%"), Some(0.0));
+        assert_eq!(memory_value(&values, "mem_pagefile"), Some(0.0));
+        // 空きが合計より多くても (取得のずれ) 使用量は 0
+        let values = get_memory_values(&TccMemoryStatus { total: GIB, available: 2 * GIB, ..Default::default() });
+        assert_eq!(memory_value(&values, "mem_used"), Some(0.0));
+    }
+
+    fn power_status(ac_line: u8, battery_flag: u8, battery_percent: u8, battery_seconds: u32) -> TccPowerStatus {
+        TccPowerStatus { ac_line, battery_flag, battery_percent, battery_seconds }
+    }
+
+    fn power_text(status: &TccPowerStatus, name: &str) -> Option<String> {
+        get_power_texts(status).into_iter().find(|x| x.0 == name).map(|x| x.1)
+    }
+
+    #[test]
+    fn desktop_has_no_battery_values() {
+        // バッテリーなし (128), 不明 (255), 残量不明
+        for status in [power_status(1, 128, 255, u32::MAX), power_status(1, 255, 255, u32::MAX), power_status(0, 1, 255, u32::MAX)] {
+            assert!(get_power_values(&status).is_empty(), "{status:?}");
+            assert_eq!(power_text(&status, "battery_state").as_deref(), Some(""), "{status:?}");
+        }
+        assert_eq!(power_text(&power_status(1, 128, 255, u32::MAX), "ac").as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn battery_state_and_time() {
+        // 充電中 (8 | 高 1)
+        let status = power_status(1, 9, 60, 5400);
+        assert_eq!(get_battery_state(&status), "charging");
+        assert_eq!(get_power_values(&status)[0], ("battery".to_string(), 60.0));
+        assert!(get_power_values(&status)[1].1.is_nan());
+        // 放電中
+        let status = power_status(0, 2, 30, 5400);
+        assert_eq!(get_battery_state(&status), "discharging");
+        assert_eq!(get_power_values(&status)[1], ("battery_time".to_string(), 5400.0));
+        assert_eq!(power_text(&status, "ac").as_deref(), Some("0"));
+        // 放電中で残り時間を計算中
+        assert!(get_power_values(&power_status(0, 2, 30, u32::MAX))[1].1.is_nan());
+        // 電源につながっていて充電していない
+        let status = power_status(1, 1, 100, u32::MAX);
+        assert_eq!(get_battery_state(&status), "full");
+        assert!(get_power_values(&status)[1].1.is_nan());
+        let status = power_status(1, 1, 80, 3600);
+        assert_eq!(get_battery_state(&status), "not_charging");
+        assert!(get_power_values(&status)[1].1.is_nan());
+    }
+
+    #[test]
+    fn unknown_ac_line_is_empty() {
+        let status = power_status(255, 2, 50, 1800);
+        assert_eq!(power_text(&status, "ac").as_deref(), Some(""));
+        assert_eq!(get_battery_state(&status), "discharging");
+    }
+}
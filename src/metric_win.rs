@@ -0,0 +1,1000 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, ToSocketAddrs},
+    sync::mpsc::Sender,
+    time::{Duration, Instant},
+};
+use windows::{
+    core::*,
+    Win32::Foundation::*,
+    Win32::NetworkManagement::IpHelper::*,
+    Win32::NetworkManagement::Ndis::IfOperStatusUp,
+    Win32::Networking::WinSock::{AF_INET, AF_INET6, AF_UNSPEC, SOCKADDR_IN, SOCKADDR_IN6, SOCKET_ADDRESS},
+    Win32::Storage::FileSystem::*,
+    Win32::System::Diagnostics::ToolHelp::*,
+    Win32::System::Performance::*,
+    Win32::System::Power::*,
+    Win32::System::ProcessStatus::*,
+    Win32::System::SystemInformation::*,
+    Win32::System::Threading::*,
+};
+use anyhow::*;
+use wmi::*;
+use winreg::enums::*;
+use winreg::RegKey;
+use crate::app::convert_utf16_null;
+use crate::gpu;
+use crate::metric::{self, MetricSource};
+use crate::network;
+use crate::probe;
+use crate::process;
+
+// Windows の MetricSource  (PDH, WMI)
+
+fn get_pdh_formatted_value(counter_handle: isize) -> Option<f64> {
+    let mut counter_value = PDH_FMT_COUNTERVALUE::default();
+    let ret = unsafe {
+        PdhGetFormattedCounterValue(
+            counter_handle,
+            PDH_FMT_DOUBLE, //PDH_FMT_DOUBLE, PDH_FMT_LARGE, PDH_FMT_LONG
+            None,
+            &mut counter_value
+        )
+    };
+    if ret == 0 {
+        Some(unsafe { counter_value.Anonymous.doubleValue })
+    } else {
+        None
+    }
+}
+
+// ワイルドカードのカウンタ  "\\Processor Information(*)\\..." => [("0,0", 12.3), ("0,1", 4.5), ...]
+fn get_pdh_formatted_array(counter_handle: isize) -> Vec<(String, f64)> {
+    let mut buffer_size: u32 = 0;
+    let mut item_count: u32 = 0;
+    let ret = unsafe {
+        PdhGetFormattedCounterArrayW(
+            counter_handle,
+            PDH_FMT_DOUBLE,
+            &mut buffer_size,
+            &mut item_count,
+            None
+        )
+    };
+    if ret != PDH_MORE_DATA || buffer_size == 0 {
+        return Vec::new();
+    }
+    // 項目の後ろにインスタンス名の文字列が入るので、バイト数で確保する
+    let item_size = std::mem::size_of::<PDH_FMT_COUNTERVALUE_ITEM_W>();
+    let mut buffer: Vec<PDH_FMT_COUNTERVALUE_ITEM_W> = Vec::with_capacity((buffer_size as usize).div_ceil(item_size));
+    let ret = unsafe {
+        PdhGetFormattedCounterArrayW(
+            counter_handle,
+            PDH_FMT_DOUBLE,
+            &mut buffer_size,
+            &mut item_count,
+            Some(buffer.as_mut_ptr())
+        )
+    };
+    if ret != 0 {
+        return Vec::new();
+    }
+    unsafe {
+        std::slice::from_raw_parts(buffer.as_ptr(), item_count as usize)
+            .iter()
+            .filter(|x| x.FmtValue.CStatus == 0)
+            .map(|x| (x.szName.to_string().unwrap_or_default(), x.FmtValue.Anonymous.doubleValue))
+            .collect()
+    }
+}
+
+// PDH の戻り値  ERROR_SUCCESS 以外 => Err  (init で Err を返すと、その source は使わない)
+fn check_pdh_status(ret: u32, what: &str) -> anyhow::Result<()> {
+    if ret == ERROR_SUCCESS.0 {
+        Ok(())
+    } else {
+        Err(anyhow!(format!("{what} : 0x{ret:08X}")))
+    }
+}
+
+fn open_pdh_query(query_handle: &mut isize) -> anyhow::Result<()> {
+    let ret = unsafe {
+        PdhOpenQueryW(
+            None,
+            0,
+            query_handle
+        )
+    };
+    check_pdh_status(ret, "PdhOpenQueryW")
+}
+
+fn add_pdh_counter(query_handle: isize, counter_path: &str, counter_handle: &mut isize) -> anyhow::Result<()> {
+    let ret = unsafe {
+        PdhAddCounterW(
+            query_handle,
+            PCWSTR(convert_utf16_null(counter_path).as_ptr()),
+            0,
+            counter_handle
+        )
+    };
+    check_pdh_status(ret, &format!("PdhAddCounterW {counter_path}"))
+}
+
+fn collect_pdh_query(query_handle: isize) -> anyhow::Result<()> {
+    let ret = unsafe { PdhCollectQueryData(query_handle) };
+    check_pdh_status(ret, "PdhCollectQueryData")
+}
+
+// {cpu}, {cpu0}, {cpu1}, ..., {cpu_max}, {cpu_count}
+// 論理プロセッサが64を超えるとプロセッサグループに分かれる  インスタンス名は "グループ,番号"
+#[derive(Debug, Default)]
+pub struct TccCpuSource {
+    pdh_query_handle: isize,
+    pdh_counter_handle: isize,
+    pdh_core_counter_handle: isize,
+}
+
+impl MetricSource for TccCpuSource {
+    fn provides(&self, name: &str) -> bool {
+        match name.strip_prefix("cpu") {
+            Some("" | "_max" | "_count") => true,
+            Some(x) => x.chars().all(|c| c.is_ascii_digit()),
+            None => false,
+        }
+    }
+
+    fn init(&mut self) -> anyhow::Result<()> {
+        open_pdh_query(&mut self.pdh_query_handle)?;
+        add_pdh_counter(self.pdh_query_handle, r#"\Processor Information(_Total)\% Processor Utility"#, &mut self.pdh_counter_handle)?;
+        add_pdh_counter(self.pdh_query_handle, r#"\Processor Information(*)\% Processor Utility"#, &mut self.pdh_core_counter_handle)?;
+        collect_pdh_query(self.pdh_query_handle)
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        unsafe {
+            PdhCollectQueryData(self.pdh_query_handle);
+        }
+        let value = get_pdh_formatted_value(self.pdh_counter_handle).unwrap_or(0.0);
+        let mut values = vec![("cpu".to_string(), value.min(100.0).round())];
+        values.extend(metric::get_core_values(&get_pdh_formatted_array(self.pdh_core_counter_handle)));
+        values
+    }
+
+    fn close(&mut self) {
+        unsafe {
+            PdhRemoveCounter(self.pdh_core_counter_handle);
+            PdhRemoveCounter(self.pdh_counter_handle);
+            PdhCloseQuery(self.pdh_query_handle);
+        }
+    }
+}
+
+// d3dkmthk.h  (windows 0.48 にはないので gdi32.dll から直接使う)
+//   KMTQAITYPE_ADAPTERPERFDATA はタスクマネージャーの GPU 温度と同じ値  (WDDM 2.4 以降)
+const KMTQAITYPE_ADAPTERPERFDATA: i32 = 62;
+
+#[repr(C)]
+struct D3dkmtOpenAdapterFromLuid {
+    adapter_luid: LUID,
+    adapter_handle: u32,
+}
+
+#[repr(C)]
+struct D3dkmtCloseAdapter {
+    adapter_handle: u32,
+}
+
+#[repr(C)]
+struct D3dkmtQueryAdapterInfo {
+    adapter_handle: u32,
+    query_type: i32,
+    private_driver_data: *mut std::ffi::c_void,
+    private_driver_data_size: u32,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct D3dkmtAdapterPerfData {
+    physical_adapter_index: u32,
+    memory_frequency: u64,
+    max_memory_frequency: u64,
+    max_memory_frequency_oc: u64,
+    memory_bandwidth: u64,
+    pcie_bandwidth: u64,
+    fan_rpm: u32,
+    power: u32,
+    temperature: u32,  // 0.1 ℃
+    power_state_override: u8,
+}
+
+#[link(name = "gdi32")]
+extern "system" {
+    fn D3DKMTOpenAdapterFromLuid(open_adapter: *mut D3dkmtOpenAdapterFromLuid) -> i32;
+    fn D3DKMTCloseAdapter(close_adapter: *const D3dkmtCloseAdapter) -> i32;
+    fn D3DKMTQueryAdapterInfo(query_adapter_info: *const D3dkmtQueryAdapterInfo) -> i32;
+}
+
+fn open_d3dkmt_adapter(luid: u64) -> Option<u32> {
+    let mut open_adapter = D3dkmtOpenAdapterFromLuid {
+        adapter_luid: LUID {
+            LowPart: (luid & 0xFFFF_FFFF) as u32,
+            HighPart: (luid >> 32) as i32,
+        },
+        adapter_handle: 0,
+    };
+    let ret = unsafe { D3DKMTOpenAdapterFromLuid(&mut open_adapter) };
+    if ret == 0 {
+        Some(open_adapter.adapter_handle)
+    } else {
+        None
+    }
+}
+
+// ℃  (ドライバが対応していない => None)
+fn get_d3dkmt_temperature(adapter_handle: u32) -> Option<f64> {
+    let mut perf_data = D3dkmtAdapterPerfData::default();
+    let query_adapter_info = D3dkmtQueryAdapterInfo {
+        adapter_handle,
+        query_type: KMTQAITYPE_ADAPTERPERFDATA,
+        private_driver_data: &mut perf_data as *mut D3dkmtAdapterPerfData as *mut std::ffi::c_void,
+        private_driver_data_size: std::mem::size_of::<D3dkmtAdapterPerfData>() as u32,
+    };
+    let ret = unsafe { D3DKMTQueryAdapterInfo(&query_adapter_info) };
+    if ret == 0 && perf_data.temperature > 0 {
+        Some(perf_data.temperature as f64 / 10.0)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug)]
+struct TccGpu {
+    name: String,
+    luid: String,  // "0x00000000_0x0000D1A2"
+    // D3DKMT adapter handle
+    adapter_handle: Option<u32>,
+}
+
+// PNPDeviceID => VideoID => AdapterLuid
+fn get_adapter_luid(pnp_device_id: &str) -> Option<u64> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let path = r#"SYSTEM\CurrentControlSet\Enum\"#.to_string() + pnp_device_id + r#"\Device Parameters"#;
+    let video_id: String = hklm.open_subkey(path).ok()?.get_value("VideoID").ok()?;
+    let path = r#"SOFTWARE\Microsoft\DirectX\"#.to_string() + &video_id;
+    hklm.open_subkey(path).ok()?.get_value("AdapterLuid").ok()
+}
+
+// {gpu0}, {gpu1}, ...  {gpu0:3d}, {gpu0:copy}, {gpu0:video_decode}, ...
+// {gpu0_name}, {gpu0_dedicated}, {gpu0_shared}, {gpu0_temp}
+#[derive(Debug, Default)]
+pub struct TccGpuSource {
+    setting: gpu::TccGpuSetting,
+    pdh_query_handle: isize,
+    pdh_counter_handle: isize,
+    pdh_dedicated_counter_handle: isize,
+    pdh_shared_counter_handle: isize,
+    tcc_gpu_vec: Vec<TccGpu>,
+    // GPU Adapter Memory に出ている luid  (変わったら GPU の一覧を作り直す)
+    luid_watcher: gpu::TccGpuLuidWatcher,
+    // 一度出た placeholder name  (使っているプロセスがなくなったエンジンは 0、なくなった GPU は NaN にする)
+    names: Vec<String>,
+    max_gpu_count: usize,
+}
+
+impl TccGpuSource {
+    pub fn new(setting: gpu::TccGpuSetting) -> TccGpuSource {
+        TccGpuSource {
+            setting,
+            ..Default::default()
+        }
+    }
+
+    // WMI の順番で gpu0, gpu1, ...  GLOBAL_GPU_ADAPTER も更新する
+    // VideoID, AdapterLuid がないアダプタ (リモートデスクトップなど) は数えない
+    // WMI が失敗したときは今の一覧のまま
+    fn enumerate_gpus(&mut self) -> anyhow::Result<()> {
+        let com_con = COMLibrary::new()?;
+        let wmi_con = WMIConnection::new(com_con)?;
+        let wmi_results: Vec<HashMap<String, Variant>> = wmi_con.raw_query("SELECT Name, PNPDeviceID FROM Win32_VideoController")?;
+        let mut tcc_gpu_vec: Vec<TccGpu> = Vec::new();
+        for wmi_result in wmi_results.iter() {
+            let Some(Variant::String(pnp_device_id)) = wmi_result.get("PNPDeviceID") else {
+                continue;
+            };
+            let Some(luid) = get_adapter_luid(pnp_device_id) else {
+                continue;
+            };
+            let name = match wmi_result.get("Name") {
+                Some(Variant::String(x)) => x.to_string(),
+                _ => "".to_string(),
+            };
+            tcc_gpu_vec.push(TccGpu {
+                name,
+                luid: gpu::format_luid(luid),
+                adapter_handle: open_d3dkmt_adapter(luid),
+            });
+        }
+        self.close_adapters();
+        self.tcc_gpu_vec = tcc_gpu_vec;
+        self.max_gpu_count = self.max_gpu_count.max(self.tcc_gpu_vec.len());
+        *gpu::GLOBAL_GPU_ADAPTER.lock().unwrap() = self.tcc_gpu_vec
+            .iter()
+            .map(|x| gpu::TccGpuAdapter {
+                name: x.name.to_string(),
+                luid: x.luid.to_string(),
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn close_adapters(&mut self) {
+        for adapter_handle in self.tcc_gpu_vec.drain(..).filter_map(|x| x.adapter_handle) {
+            unsafe {
+                D3DKMTCloseAdapter(&D3dkmtCloseAdapter { adapter_handle });
+            }
+        }
+    }
+}
+
+impl MetricSource for TccGpuSource {
+    fn provides(&self, name: &str) -> bool {
+        if name.starts_with("gpu:") {
+            return true;
+        }
+        match gpu::split_gpu_name(name) {
+            Some((_, "" | "_name" | "_dedicated" | "_shared" | "_temp")) => true,
+            Some((_, x)) => x.strip_prefix(':').is_some_and(|x| x.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')),
+            None => false,
+        }
+    }
+
+    fn init(&mut self) -> anyhow::Result<()> {
+        // get gpu info
+        self.enumerate_gpus()?;
+
+        // init gpu performance counter
+        //   GPU Engine のインスタンスはプロセスごと  ワイルドカードなら増減に追従する
+        open_pdh_query(&mut self.pdh_query_handle)?;
+        for (counter_path, handle) in [
+            (r#"\GPU Engine(*)\Utilization Percentage"#, &mut self.pdh_counter_handle),
+            (r#"\GPU Adapter Memory(*)\Dedicated Usage"#, &mut self.pdh_dedicated_counter_handle),
+            (r#"\GPU Adapter Memory(*)\Shared Usage"#, &mut self.pdh_shared_counter_handle),
+        ] {
+            add_pdh_counter(self.pdh_query_handle, counter_path, handle)?;
+        }
+        collect_pdh_query(self.pdh_query_handle)
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        unsafe {
+            PdhCollectQueryData(self.pdh_query_handle);
+        }
+        let luids: Vec<String> = self.tcc_gpu_vec.iter().map(|x| x.luid.to_string()).collect();
+        let mut values = gpu::get_gpu_values(&get_pdh_formatted_array(self.pdh_counter_handle), &luids, self.setting.aggregation);
+        let dedicated = get_pdh_formatted_array(self.pdh_dedicated_counter_handle);
+        let shared = get_pdh_formatted_array(self.pdh_shared_counter_handle);
+        values.extend(gpu::get_gpu_memory_values(&dedicated, &shared, &luids));
+        for (i, tcc_gpu) in self.tcc_gpu_vec.iter().enumerate() {
+            let temperature = tcc_gpu.adapter_handle.and_then(get_d3dkmt_temperature);
+            values.push((format!("gpu{i}_temp"), temperature.unwrap_or(f64::NAN)));
+        }
+        for name in self.names.iter() {
+            if !values.iter().any(|x| &x.0 == name) {
+                let is_engine = gpu::split_gpu_name(name).is_some_and(|(i, x)| i < self.tcc_gpu_vec.len() && x.starts_with(':'));
+                values.push((name.to_string(), if is_engine { 0.0 } else { f64::NAN }));
+            }
+        }
+        for (name, _) in values.iter() {
+            if !self.names.contains(name) {
+                self.names.push(name.to_string());
+            }
+        }
+
+        // eGPU, 仮想ディスプレイアダプタなどが増減した
+        let mut counter_luids: Vec<String> = dedicated
+            .iter()
+            .filter_map(|x| gpu::parse_adapter_memory_instance(&x.0))
+            .collect();
+        counter_luids.sort();
+        counter_luids.dedup();
+        let now = Instant::now();
+        if self.luid_watcher.is_changed(&counter_luids, now) {
+            let is_ok = self.enumerate_gpus().is_ok();
+            self.luid_watcher.set_result(&counter_luids, is_ok, now);
+        }
+        values
+    }
+
+    // なくなった GPU は ""
+    fn get_texts(&self) -> Vec<(String, String)> {
+        (0..self.max_gpu_count)
+            .map(|i| (format!("gpu{i}_name"), self.tcc_gpu_vec.get(i).map(|x| x.name.to_string()).unwrap_or_default()))
+            .collect()
+    }
+
+    fn close(&mut self) {
+        unsafe {
+            PdhRemoveCounter(self.pdh_counter_handle);
+            PdhRemoveCounter(self.pdh_dedicated_counter_handle);
+            PdhRemoveCounter(self.pdh_shared_counter_handle);
+            PdhCloseQuery(self.pdh_query_handle);
+        }
+        self.close_adapters();
+    }
+}
+
+// MIB_IF_ROW2 > InterfaceAndOperStatusFlags
+const IF_FLAG_HARDWARE_INTERFACE: u8 = 0x01;
+const IF_FLAG_FILTER_INTERFACE: u8 = 0x02;
+
+fn get_net_adapters() -> Vec<network::TccNetAdapter> {
+    let mut adapters: Vec<network::TccNetAdapter> = Vec::new();
+    unsafe {
+        let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
+        if GetIfTable2(&mut table) != NO_ERROR || table.is_null() {
+            return adapters;
+        }
+        let rows = std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+        for row in rows {
+            let flags = row.InterfaceAndOperStatusFlags._bitfield;
+            adapters.push(network::TccNetAdapter {
+                id: row.InterfaceLuid.Value,
+                alias: String::from_utf16_lossy(&row.Alias).trim_end_matches('\0').to_string(),
+                description: String::from_utf16_lossy(&row.Description).trim_end_matches('\0').to_string(),
+                is_hardware: flags & IF_FLAG_HARDWARE_INTERFACE != 0,
+                is_loopback: row.Type == IF_TYPE_SOFTWARE_LOOPBACK,
+                is_filter: flags & IF_FLAG_FILTER_INTERFACE != 0,
+                received: row.InOctets,
+                sent: row.OutOctets,
+            });
+        }
+        FreeMibTable(table as *const _);
+    }
+    adapters
+}
+
+// {net_down}, {net_up}  bytes/s
+#[derive(Debug, Default)]
+pub struct TccNetSource {
+    setting: network::TccNetworkSetting,
+    counter: network::TccNetCounter,
+    last_sampled: Option<Instant>,
+}
+
+impl TccNetSource {
+    pub fn new(setting: network::TccNetworkSetting) -> TccNetSource {
+        TccNetSource {
+            setting,
+            ..Default::default()
+        }
+    }
+}
+
+impl MetricSource for TccNetSource {
+    fn provides(&self, name: &str) -> bool {
+        name == "net_down" || name == "net_up"
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        let adapters: Vec<network::TccNetAdapter> = get_net_adapters()
+            .into_iter()
+            .filter(|x| self.setting.is_match(x))
+            .collect();
+        let seconds = self.last_sampled.map(|x| x.elapsed().as_secs_f64()).unwrap_or(0.0);
+        self.last_sampled = Some(Instant::now());
+        let (down, up) = self.counter.update(&adapters, seconds);
+        vec![("net_down".to_string(), down), ("net_up".to_string(), up)]
+    }
+}
+
+// GetDriveTypeW
+const DRIVE_REMOVABLE: u32 = 2;
+const DRIVE_FIXED: u32 = 3;
+const DRIVE_RAMDISK: u32 = 6;
+
+// {disk:C:free}, {disk:C:used}, {disk:C:total}, {disk:C:used%}, {disk:C:free%}
+// ネットワークドライブ、CD/DVD は対象外  (応答が遅いことがある)
+#[derive(Debug, Default)]
+pub struct TccDiskSpaceSource {}
+
+impl MetricSource for TccDiskSpaceSource {
+    fn provides(&self, name: &str) -> bool {
+        name.starts_with("disk:")
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        let mut values: Vec<(String, f64)> = Vec::new();
+        let drives = unsafe { GetLogicalDrives() };
+        for (i, letter) in ('A'..='Z').enumerate() {
+            if drives & (1 << i) == 0 {
+                continue;
+            }
+            let root = format!(r#"{letter}:\"#);
+            let root_utf16 = convert_utf16_null(&root);
+            let drive_type = unsafe { GetDriveTypeW(PCWSTR(root_utf16.as_ptr())) };
+            if ![DRIVE_REMOVABLE, DRIVE_FIXED, DRIVE_RAMDISK].contains(&drive_type) {
+                continue;
+            }
+            let mut free: u64 = 0;
+            let mut total: u64 = 0;
+            let ret = unsafe {
+                GetDiskFreeSpaceExW(
+                    PCWSTR(root_utf16.as_ptr()),
+                    Some(&mut free),
+                    Some(&mut total),
+                    None
+                )
+            };
+            if ret.as_bool() {
+                values.extend(metric::get_disk_space_values(&root, total, free));
+            }
+        }
+        values
+    }
+}
+
+// {disk_read}, {disk_write}  bytes/s  (すべての物理ディスク)
+// {disk_read:0}  物理ディスク0, {disk_read:C}  ドライブC
+#[derive(Debug, Default)]
+pub struct TccDiskIoSource {
+    pdh_query_handle: isize,
+    // (placeholder name, PhysicalDisk or not, handle)
+    pdh_counter_handles: Vec<(&'static str, bool, isize)>,
+}
+
+impl MetricSource for TccDiskIoSource {
+    fn provides(&self, name: &str) -> bool {
+        let base = name.split(':').next().unwrap_or("");
+        base == "disk_read" || base == "disk_write"
+    }
+
+    fn init(&mut self) -> anyhow::Result<()> {
+        open_pdh_query(&mut self.pdh_query_handle)?;
+        for (name, is_physical, counter_path) in [
+            ("disk_read", true, r#"\PhysicalDisk(*)\Disk Read Bytes/sec"#),
+            ("disk_write", true, r#"\PhysicalDisk(*)\Disk Write Bytes/sec"#),
+            ("disk_read", false, r#"\LogicalDisk(*)\Disk Read Bytes/sec"#),
+            ("disk_write", false, r#"\LogicalDisk(*)\Disk Write Bytes/sec"#),
+        ] {
+            let mut handle: isize = 0;
+            add_pdh_counter(self.pdh_query_handle, counter_path, &mut handle)?;
+            self.pdh_counter_handles.push((name, is_physical, handle));
+        }
+        collect_pdh_query(self.pdh_query_handle)
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        unsafe {
+            PdhCollectQueryData(self.pdh_query_handle);
+        }
+        let mut values: Vec<(String, f64)> = Vec::new();
+        for (name, is_physical, handle) in self.pdh_counter_handles.iter() {
+            for (instance, value) in get_pdh_formatted_array(*handle) {
+                if let Some(suffix) = metric::get_disk_io_suffix(&instance, *is_physical) {
+                    values.push((format!("{name}{suffix}"), value.max(0.0)));
+                }
+            }
+        }
+        values
+    }
+
+    fn close(&mut self) {
+        unsafe {
+            for (_, _, handle) in self.pdh_counter_handles.iter() {
+                PdhRemoveCounter(*handle);
+            }
+            PdhCloseQuery(self.pdh_query_handle);
+        }
+    }
+}
+
+// {mem}, {mem_used}, {mem_available}, {mem_total}, {mem_commit}, {mem_commit_limit}, {mem_pagefile}, ...
+#[derive(Debug, Default)]
+pub struct TccMemSource {
+    pdh_query_handle: isize,
+    pdh_counter_handle: isize,
+}
+
+impl MetricSource for TccMemSource {
+    fn provides(&self, name: &str) -> bool {
+        name == "mem" || name.starts_with("mem_")
+    }
+
+    fn init(&mut self) -> anyhow::Result<()> {
+        open_pdh_query(&mut self.pdh_query_handle)?;
+        add_pdh_counter(self.pdh_query_handle, r#"\Paging File(_Total)\% Usage"#, &mut self.pdh_counter_handle)
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        let mut memory_status = MEMORYSTATUSEX {
+            dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { GlobalMemoryStatusEx(&mut memory_status) }.as_bool() {
+            return Vec::new();
+        }
+        unsafe {
+            PdhCollectQueryData(self.pdh_query_handle);
+        }
+        metric::get_memory_values(&metric::TccMemoryStatus {
+            total: memory_status.ullTotalPhys,
+            available: memory_status.ullAvailPhys,
+            commit_limit: memory_status.ullTotalPageFile,
+            commit_available: memory_status.ullAvailPageFile,
+            pagefile_usage: get_pdh_formatted_value(self.pdh_counter_handle),
+        })
+    }
+
+    fn close(&mut self) {
+        unsafe {
+            PdhRemoveCounter(self.pdh_counter_handle);
+            PdhCloseQuery(self.pdh_query_handle);
+        }
+    }
+}
+
+// {battery}, {battery_state}, {battery_time}, {ac}
+#[derive(Debug, Default)]
+pub struct TccBatterySource {
+    texts: Vec<(String, String)>,
+}
+
+impl MetricSource for TccBatterySource {
+    fn provides(&self, name: &str) -> bool {
+        matches!(name, "battery" | "battery_state" | "battery_time" | "ac")
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        let mut power_status = SYSTEM_POWER_STATUS::default();
+        if !unsafe { GetSystemPowerStatus(&mut power_status) }.as_bool() {
+            return Vec::new();
+        }
+        let status = metric::TccPowerStatus {
+            ac_line: power_status.ACLineStatus,
+            battery_flag: power_status.BatteryFlag,
+            battery_percent: power_status.BatteryLifePercent,
+            battery_seconds: power_status.BatteryLifeTime,
+        };
+        self.texts = metric::get_power_texts(&status);
+        metric::get_power_values(&status)
+    }
+
+    fn get_texts(&self) -> Vec<(String, String)> {
+        self.texts.clone()
+    }
+}
+
+// GetActiveProcessorCount
+const ALL_PROCESSOR_GROUPS: u16 = 0xFFFF;
+
+fn filetime_to_u64(filetime: &FILETIME) -> u64 {
+    ((filetime.dwHighDateTime as u64) << 32) | filetime.dwLowDateTime as u64
+}
+
+// (pid, image name)
+fn get_process_names() -> Vec<(u32, String)> {
+    let mut processes: Vec<(u32, String)> = Vec::new();
+    unsafe {
+        let std::result::Result::Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return processes;
+        };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut ret = Process32FirstW(snapshot, &mut entry);
+        while ret.as_bool() {
+            let len = entry.szExeFile.iter().position(|x| *x == 0).unwrap_or(entry.szExeFile.len());
+            processes.push((entry.th32ProcessID, String::from_utf16_lossy(&entry.szExeFile[..len])));
+            ret = Process32NextW(snapshot, &mut entry);
+        }
+        CloseHandle(snapshot);
+    }
+    processes
+}
+
+// 開けないプロセス (権限がない) は None
+fn get_process(pid: u32, image_name: String) -> Option<process::TccProcess> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid).ok()?;
+        let mut created = FILETIME::default();
+        let mut exited = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        let mut memory_counters = PROCESS_MEMORY_COUNTERS {
+            cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+            ..Default::default()
+        };
+        let is_times = GetProcessTimes(handle, &mut created, &mut exited, &mut kernel, &mut user).as_bool();
+        let is_memory = GetProcessMemoryInfo(handle, &mut memory_counters, memory_counters.cb).as_bool();
+        CloseHandle(handle);
+        if !is_times {
+            return None;
+        }
+        Some(process::TccProcess {
+            pid,
+            image_name,
+            created: filetime_to_u64(&created),
+            cpu_time: filetime_to_u64(&kernel) + filetime_to_u64(&user),
+            memory: if is_memory { memory_counters.WorkingSetSize as u64 } else { 0 },
+        })
+    }
+}
+
+// {proc:cargo.exe:cpu}, {proc:code.exe:mem}, {proc:count:rustc.exe}
+// ラベルで使っている名前に合うプロセスだけ調べる
+#[derive(Debug, Default)]
+pub struct TccProcSource {
+    names: Vec<String>,
+    selectors: Vec<process::TccProcSelector>,
+    counter: process::TccProcCounter,
+    cpu_count: usize,
+}
+
+impl MetricSource for TccProcSource {
+    fn provides(&self, name: &str) -> bool {
+        process::parse_proc_name(name).is_some()
+    }
+
+    fn set_names(&mut self, names: &[String]) {
+        self.names = names.to_vec();
+        self.selectors = names.iter().filter_map(|x| process::parse_proc_name(x)).map(|x| x.0).collect();
+    }
+
+    fn init(&mut self) -> anyhow::Result<()> {
+        self.cpu_count = unsafe { GetActiveProcessorCount(ALL_PROCESSOR_GROUPS) } as usize;
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        let snapshot: Vec<(u32, String)> = get_process_names()
+            .into_iter()
+            .filter(|(_, name)| self.selectors.iter().any(|x| x.is_match(name)))
+            .collect();
+        let processes: Vec<process::TccProcess> = snapshot.iter().filter_map(|(pid, name)| get_process(*pid, name.to_string())).collect();
+        let image_names: Vec<String> = snapshot.into_iter().map(|x| x.1).collect();
+        let now = filetime_to_u64(&unsafe { GetSystemTimeAsFileTime() });
+        let (used, elapsed) = self.counter.update(&processes, now);
+        process::get_proc_values(&self.names, &image_names, &processes, &used, elapsed, self.cpu_count)
+    }
+}
+
+fn get_computer_name(name_type: COMPUTER_NAME_FORMAT) -> String {
+    let mut size: u32 = 0;
+    unsafe {
+        GetComputerNameExW(name_type, PWSTR::null(), &mut size);
+        let mut buffer: Vec<u16> = vec![0; size as usize];
+        if !GetComputerNameExW(name_type, PWSTR(buffer.as_mut_ptr()), &mut size).as_bool() {
+            return "".to_string();
+        }
+        String::from_utf16_lossy(&buffer[..size as usize])
+    }
+}
+
+// "22631.4317"
+fn get_os_build() -> String {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let std::result::Result::Ok(key) = hklm.open_subkey(r#"SOFTWARE\Microsoft\Windows NT\CurrentVersion"#) else {
+        return "".to_string();
+    };
+    let build: String = key.get_value("CurrentBuild").unwrap_or_default();
+    match key.get_value::<u32, _>("UBR") {
+        std::result::Result::Ok(ubr) if !build.is_empty() => format!("{build}.{ubr}"),
+        _ => build,
+    }
+}
+
+fn socket_address_to_ip(address: &SOCKET_ADDRESS) -> Option<IpAddr> {
+    if address.lpSockaddr.is_null() {
+        return None;
+    }
+    unsafe {
+        let family = (*address.lpSockaddr).sa_family;
+        if family == AF_INET {
+            let sockaddr = &*(address.lpSockaddr as *const SOCKADDR_IN);
+            Some(IpAddr::V4(Ipv4Addr::from(u32::from_be(sockaddr.sin_addr.S_un.S_addr))))
+        } else if family == AF_INET6 {
+            let sockaddr = &*(address.lpSockaddr as *const SOCKADDR_IN6);
+            Some(IpAddr::V6(Ipv6Addr::from(sockaddr.sin6_addr.u.Byte)))
+        } else {
+            None
+        }
+    }
+}
+
+fn get_ip_adapters() -> Vec<network::TccIpAdapter> {
+    let mut adapters: Vec<network::TccIpAdapter> = Vec::new();
+    let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER | GAA_FLAG_INCLUDE_GATEWAYS;
+    unsafe {
+        // 15KB から始めるのが推奨
+        let mut size: u32 = 15 * 1024;
+        let mut buffer: Vec<u64> = Vec::new();
+        let mut ret = ERROR_BUFFER_OVERFLOW.0;
+        for _ in 0..3 {
+            buffer = vec![0; (size as usize).div_ceil(8)];
+            ret = GetAdaptersAddresses(AF_UNSPEC.0 as u32, flags, None, Some(buffer.as_mut_ptr() as *mut IP_ADAPTER_ADDRESSES_LH), &mut size);
+            if ret != ERROR_BUFFER_OVERFLOW.0 {
+                break;
+            }
+        }
+        if ret != NO_ERROR.0 {
+            return adapters;
+        }
+        let mut p = buffer.as_ptr() as *const IP_ADAPTER_ADDRESSES_LH;
+        while !p.is_null() {
+            let row = &*p;
+            let mut adapter = network::TccIpAdapter {
+                alias: row.FriendlyName.to_string().unwrap_or_default(),
+                description: row.Description.to_string().unwrap_or_default(),
+                is_up: row.OperStatus == IfOperStatusUp,
+                is_loopback: row.IfType == IF_TYPE_SOFTWARE_LOOPBACK,
+                ..Default::default()
+            };
+            let mut address = row.FirstUnicastAddress;
+            while !address.is_null() {
+                adapter.addresses.extend(socket_address_to_ip(&(*address).Address));
+                address = (*address).Next;
+            }
+            let mut address = row.FirstGatewayAddress;
+            while !address.is_null() {
+                adapter.gateways.extend(socket_address_to_ip(&(*address).Address));
+                address = (*address).Next;
+            }
+            adapters.push(adapter);
+            p = row.Next;
+        }
+    }
+    adapters
+}
+
+// {hostname}, {user}, {domain}, {os_build}, {uptime}, {boot_time}, {local_ip}, {local_ip:Wi-Fi}, {process_count}
+// ほとんど変わらない値なので、既定の取得間隔は長め
+#[derive(Debug, Default)]
+pub struct TccSysInfoSource {
+    names: Vec<String>,
+    texts: Vec<(String, String)>,
+}
+
+impl MetricSource for TccSysInfoSource {
+    fn provides(&self, name: &str) -> bool {
+        matches!(name, "hostname" | "user" | "domain" | "os_build" | "uptime" | "boot_time" | "local_ip" | "process_count")
+            || name.starts_with("local_ip:")
+    }
+
+    fn set_names(&mut self, names: &[String]) {
+        self.names = names.to_vec();
+    }
+
+    fn default_interval(&self) -> u64 {
+        60
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        let mut values: Vec<(String, f64)> = Vec::new();
+        self.texts.clear();
+        let ip_adapters = if self.names.iter().any(|x| x.starts_with("local_ip")) { get_ip_adapters() } else { Vec::new() };
+        for name in self.names.iter() {
+            match name.as_str() {
+                "hostname" => self.texts.push((name.to_string(), get_computer_name(ComputerNameDnsHostname))),
+                // ドメインに参加していなければ空
+                "domain" => self.texts.push((name.to_string(), get_computer_name(ComputerNameDnsDomain))),
+                "user" => self.texts.push((name.to_string(), std::env::var("USERNAME").unwrap_or_default())),
+                "os_build" => self.texts.push((name.to_string(), get_os_build())),
+                // {uptime} は boot_time と今の時刻から計算する (取得間隔が長くても毎秒進む)
+                "uptime" | "boot_time" => {
+                    let uptime_millis = unsafe { GetTickCount64() } as i64;
+                    let boot_time = chrono::Utc::now().timestamp_millis() - uptime_millis;
+                    values.push(("boot_time".to_string(), (boot_time as f64 / 1000.0).round()));
+                }
+                "process_count" => values.push((name.to_string(), get_process_names().len() as f64)),
+                _ => {
+                    let adapter = name.strip_prefix("local_ip").unwrap_or("").trim_start_matches(':');
+                    let ip = network::get_local_ip(&ip_adapters, adapter).map(|x| x.to_string()).unwrap_or_default();
+                    self.texts.push((name.to_string(), ip));
+                }
+            }
+        }
+        values
+    }
+
+    fn get_texts(&self) -> Vec<(String, String)> {
+        self.texts.clone()
+    }
+}
+
+// ICMP echo (IPv4)  応答時間 (ms)
+fn ping(address: Ipv4Addr, timeout: Duration) -> Option<f64> {
+    let request_data = [0u8; 32];
+    // ICMP_ECHO_REPLY + request data + 8 (ICMP error)
+    let mut reply_buffer: Vec<u64> = vec![0; (std::mem::size_of::<ICMP_ECHO_REPLY>() + request_data.len() + 8).div_ceil(8)];
+    unsafe {
+        let handle = IcmpCreateFile().ok()?;
+        let ret = IcmpSendEcho(
+            handle,
+            u32::from_ne_bytes(address.octets()),
+            request_data.as_ptr() as *const _,
+            request_data.len() as u16,
+            None,
+            reply_buffer.as_mut_ptr() as *mut _,
+            (reply_buffer.len() * 8) as u32,
+            timeout.as_millis() as u32,
+        );
+        IcmpCloseHandle(handle);
+        let reply = &*(reply_buffer.as_ptr() as *const ICMP_ECHO_REPLY);
+        // IP_SUCCESS
+        (ret > 0 && reply.Status == 0).then_some(reply.RoundTripTime as f64)
+    }
+}
+
+// "gateway" => デフォルトゲートウェイ
+fn resolve_probe_host(host: &str) -> Option<IpAddr> {
+    if host == "gateway" {
+        return network::get_default_gateway(&get_ip_adapters());
+    }
+    if let std::result::Result::Ok(x) = host.parse::<IpAddr>() {
+        return Some(x);
+    }
+    (host, 0).to_socket_addrs().ok()?.map(|x| x.ip()).min_by_key(|x| x.is_ipv6())
+}
+
+fn probe_target(target: &probe::TccProbeTarget, timeout: Duration) -> Option<f64> {
+    match target {
+        probe::TccProbeTarget::Ping(host) => match resolve_probe_host(host)? {
+            IpAddr::V4(x) => ping(x, timeout),
+            IpAddr::V6(_) => None,
+        },
+        probe::TccProbeTarget::Tcp(host, port) if host == "gateway" => probe::probe_tcp(&resolve_probe_host(host)?.to_string(), *port, timeout),
+        probe::TccProbeTarget::Tcp(host, port) => probe::probe_tcp(host, *port, timeout),
+    }
+}
+
+// {ping:gateway}, {tcp:build-server:443}
+// 宛先ごとにスレッドを作って調べる (タイムアウトを待つ間も他の値は取得できる)。sample は最新の結果を読むだけ
+#[derive(Debug, Default)]
+pub struct TccProbeSource {
+    setting: probe::TccProbeSetting,
+    names: Vec<String>,
+    results: probe::TccProbeResults,
+    senders: Vec<Sender<i32>>,
+}
+
+impl TccProbeSource {
+    pub fn new(setting: probe::TccProbeSetting) -> TccProbeSource {
+        TccProbeSource {
+            setting,
+            ..Default::default()
+        }
+    }
+}
+
+impl MetricSource for TccProbeSource {
+    fn provides(&self, name: &str) -> bool {
+        probe::parse_probe_name(name).is_some()
+    }
+
+    fn set_names(&mut self, names: &[String]) {
+        self.names = names.to_vec();
+    }
+
+    fn init(&mut self) -> anyhow::Result<()> {
+        let mut targets: Vec<probe::TccProbeTarget> = Vec::new();
+        for (target, _) in self.names.iter().filter_map(|x| probe::parse_probe_name(x)) {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        for target in targets {
+            self.senders.push(probe::spawn_prober(target, self.setting, self.results.clone(), probe_target));
+        }
+        Ok(())
+    }
+
+    fn sample(&mut self) -> Vec<(String, f64)> {
+        probe::get_probe_values(&self.names, &self.results.lock().unwrap())
+    }
+
+    fn close(&mut self) {
+        // drop => prober threads stop
+        self.senders.clear();
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+};
+use regex::Regex;
+
+// 通信速度  config.txt > network
+//   adapter         : "Wi-Fi", "イーサネット"  (名前 or 説明、大文字小文字は区別しない)
+//   adapter_regex   : "^Intel"
+//   include_virtual : 仮想アダプタ (Hyper-V, VPN など) も合計に入れる
+// adapter, adapter_regex を省略 => 物理アダプタの合計  (ループバックは入れない)
+#[derive(Debug, Default, Clone)]
+pub struct TccNetworkSetting {
+    pub adapter: String,
+    pub adapter_regex: Option<Regex>,
+    pub include_virtual: bool,
+}
+
+// GetIfTable2 の1行
+#[derive(Debug, Default, Clone)]
+pub struct TccNetAdapter {
+    pub id: u64,  // interface luid
+    pub alias: String,  // "Wi-Fi"
+    pub description: String,  // "Intel(R) Wi-Fi 6 AX201 160MHz"
+    pub is_hardware: bool,
+    pub is_loopback: bool,
+    // フィルタドライバの行  (同じ通信を二重に数えるので常に除く)
+    pub is_filter: bool,
+    pub received: u64,  // bytes
+    pub sent: u64,  // bytes
+}
+
+impl TccNetworkSetting {
+    pub fn is_match(&self, adapter: &TccNetAdapter) -> bool {
+        if adapter.is_filter {
+            return false;
+        }
+        let names = [adapter.alias.as_str(), adapter.description.as_str()];
+        // 名前を指定したときは仮想アダプタでも数える
+        if !self.adapter.is_empty() || self.adapter_regex.is_some() {
+            return names.iter().any(|x| {
+                (!self.adapter.is_empty() && x.eq_ignore_ascii_case(&self.adapter))
+                    || self.adapter_regex.as_ref().is_some_and(|re| re.is_match(x))
+            });
+        }
+        !adapter.is_loopback && (adapter.is_hardware || self.include_virtual)
+    }
+}
+
+// 前回のバイト数との差から速度を出す
+#[derive(Debug, Default, Clone)]
+pub struct TccNetCounter {
+    // id => (received, sent)
+    last: HashMap<u64, (u64, u64)>,
+}
+
+impl TccNetCounter {
+    // (down, up) bytes/s  新しく見つかったアダプタ、カウンタが戻ったアダプタは次の回から数える
+    pub fn update(&mut self, adapters: &[TccNetAdapter], seconds: f64) -> (f64, f64) {
+        let mut down: u64 = 0;
+        let mut up: u64 = 0;
+        let mut current: HashMap<u64, (u64, u64)> = HashMap::new();
+        for adapter in adapters {
+            if let Some((received, sent)) = self.last.get(&adapter.id) {
+                down += adapter.received.saturating_sub(*received);
+                up += adapter.sent.saturating_sub(*sent);
+            }
+            current.insert(adapter.id, (adapter.received, adapter.sent));
+        }
+        self.last = current;
+        if seconds <= 0.0 {
+            return (0.0, 0.0);
+        }
+        (down as f64 / seconds, up as f64 / seconds)
+    }
+}
+
+// GetAdaptersAddresses の1行  {local_ip}
+#[derive(Debug, Default, Clone)]
+pub struct TccIpAdapter {
+    pub alias: String,  // "Wi-Fi"
+    pub description: String,
+    pub is_up: bool,
+    pub is_loopback: bool,
+    pub addresses: Vec<IpAddr>,
+    pub gateways: Vec<IpAddr>,
+}
+
+// IPv4 を優先、IPv6 のリンクローカル (fe80::) は使わない
+fn get_display_address(addresses: &[IpAddr]) -> Option<IpAddr> {
+    let is_link_local = |x: &IpAddr| match x {
+        IpAddr::V4(x) => x.is_link_local(),
+        IpAddr::V6(x) => x.segments()[0] & 0xFFC0 == 0xFE80,
+    };
+    addresses
+        .iter()
+        .filter(|x| !is_link_local(x))
+        .min_by_key(|x| x.is_ipv6())
+        .copied()
+}
+
+// {local_ip}        => デフォルトゲートウェイのある最初のアダプタのアドレス
+// {local_ip:Wi-Fi}  => そのアダプタのアドレス (名前 or 説明、大文字小文字は区別しない)
+pub fn get_local_ip(adapters: &[TccIpAdapter], adapter: &str) -> Option<IpAddr> {
+    adapters
+        .iter()
+        .filter(|x| x.is_up && !x.is_loopback)
+        .filter(|x| {
+            if adapter.is_empty() {
+                !x.gateways.is_empty()
+            } else {
+                x.alias.eq_ignore_ascii_case(adapter) || x.description.eq_ignore_ascii_case(adapter)
+            }
+        })
+        .find_map(|x| get_display_address(&x.addresses))
+}
+
+// {ping:gateway}  => デフォルトゲートウェイ (IPv4 を優先)
+pub fn get_default_gateway(adapters: &[TccIpAdapter]) -> Option<IpAddr> {
+    adapters
+        .iter()
+        .filter(|x| x.is_up && !x.is_loopback)
+        .flat_map(|x| x.gateways.iter())
+        .filter(|x| !x.is_unspecified())
+        .min_by_key(|x| x.is_ipv6())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(id: u64, alias: &str, description: &str, is_hardware: bool) -> TccNetAdapter {
+        TccNetAdapter {
+            id,
+            alias: alias.to_string(),
+            description: description.to_string(),
+            is_hardware,
+            ..Default::default()
+        }
+    }
+
+    fn adapters() -> Vec<TccNetAdapter> {
+        vec![
+            adapter(1, "Wi-Fi", "Intel(R) Wi-Fi 6 AX201 160MHz", true),
+            adapter(2, "イーサネット", "Realtek PCIe GbE Family Controller", true),
+            adapter(3, "vEthernet (Default Switch)", "Hyper-V Virtual Ethernet Adapter", false),
+            TccNetAdapter { is_loopback: true, ..adapter(4, "Loopback Pseudo-Interface 1", "Software Loopback Interface 1", false) },
+            TccNetAdapter { is_filter: true, ..adapter(5, "Wi-Fi-WFP Native MAC Layer LightWeight Filter-0000", "Intel(R) Wi-Fi 6 AX201 160MHz", true) },
+        ]
+    }
+
+    fn matched(setting: &TccNetworkSetting) -> Vec<u64> {
+        adapters().iter().filter(|x| setting.is_match(x)).map(|x| x.id).collect()
+    }
+
+    #[test]
+    fn default_setting_counts_physical_adapters() {
+        assert_eq!(matched(&TccNetworkSetting::default()), vec![1, 2]);
+        let setting = TccNetworkSetting { include_virtual: true, ..Default::default() };
+        assert_eq!(matched(&setting), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn adapter_name_and_regex() {
+        // 名前 or 説明、大文字小文字は区別しない  フィルタの行は除く
+        let setting = TccNetworkSetting { adapter: "wi-fi".to_string(), ..Default::default() };
+        assert_eq!(matched(&setting), vec![1]);
+        let setting = TccNetworkSetting { adapter: "hyper-v virtual ethernet adapter".to_string(), ..Default::default() };
+        assert_eq!(matched(&setting), vec![3]);
+        let setting = TccNetworkSetting { adapter: "Wi".to_string(), ..Default::default() };
+        assert_eq!(matched(&setting), Vec::<u64>::new());
+        let setting = TccNetworkSetting { adapter_regex: Some(Regex::new("^(Intel|Realtek)").unwrap()), ..Default::default() };
+        assert_eq!(matched(&setting), vec![1, 2]);
+        let setting = TccNetworkSetting { adapter_regex: Some(Regex::new("Loopback").unwrap()), ..Default::default() };
+        assert_eq!(matched(&setting), vec![4]);
+    }
+
+    #[test]
+    fn counter_skips_new_and_reset_adapters() {
+        let mut counter = TccNetCounter::default();
+        let mut wifi = adapter(1, "Wi-Fi", "", true);
+        wifi.received = 1000;
+        wifi.sent = 100;
+        assert_eq!(counter.update(&[wifi.clone()], 1.0), (0.0, 0.0));
+        wifi.received = 3000;
+        wifi.sent = 300;
+        assert_eq!(counter.update(&[wifi.clone()], 2.0), (1000.0, 100.0));
+        // カウンタが戻った
+        wifi.received = 10;
+        assert_eq!(counter.update(&[wifi.clone()], 1.0), (0.0, 0.0));
+        assert_eq!(counter.update(&[wifi], 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn local_ip_and_gateway() {
+        let ip = |x: &str| x.parse::<IpAddr>().unwrap();
+        let adapters = vec![
+            TccIpAdapter {
+                alias: "vEthernet".to_string(),
+                is_up: true,
+                addresses: vec![ip("172.20.0.1")],
+                ..Default::default()
+            },
+            TccIpAdapter {
+                alias: "Wi-Fi".to_string(),
+                is_up: true,
+                addresses: vec![ip("fe80::1"), ip("2001:db8::5"), ip("192.168.1.5")],
+                gateways: vec![ip("fe80::254"), ip("192.168.1.1")],
+                ..Default::default()
+            },
+        ];
+        assert_eq!(get_local_ip(&adapters, ""), Some(ip("192.168.1.5")));
+        assert_eq!(get_local_ip(&adapters, "VETHERNET"), Some(ip("172.20.0.1")));
+        assert_eq!(get_local_ip(&adapters, "Bluetooth"), None);
+        assert_eq!(get_default_gateway(&adapters), Some(ip("192.168.1.1")));
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    net::{IpAddr, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{channel, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
+    thread::spawn,
+    time::{Duration, Instant},
+};
+
+// 疎通確認  config.txt > probe
+//   interval : 宛先ごとに調べる間隔 (秒)
+//   timeout  : これより遅い応答は失敗 (ミリ秒)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct TccProbeSetting {
+    pub interval: u64,
+    pub timeout: u64,
+}
+
+impl Default for TccProbeSetting {
+    fn default() -> Self {
+        TccProbeSetting {
+            interval: 5,
+            timeout: 1000,
+        }
+    }
+}
+
+// 損失率を出す回数
+const PROBE_HISTORY_SIZE: usize = 10;
+
+// "gateway" => デフォルトゲートウェイ
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TccProbeTarget {
+    Ping(String),
+    Tcp(String, u16),
+}
+
+//   {ping:gateway}              => 応答時間 (ms)  失敗したら missing_value
+//   {ping:gateway:loss}         => 直近10回の損失率 (%)
+//   {tcp:build-server:443:ok}   => 直近の結果  1 / 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TccProbeField {
+    Latency,
+    Loss,
+    Ok,
+}
+
+// "[::1]" => "::1"
+fn unbracket(s: &str) -> &str {
+    s.strip_prefix('[').and_then(|x| x.strip_suffix(']')).unwrap_or(s)
+}
+
+// "ping:gateway" => (Ping("gateway"), Latency), "tcp:build-server:443:loss" => (Tcp("build-server", 443), Loss)
+// IPv6 のアドレスは [::1] のように書く
+pub fn parse_probe_name(name: &str) -> Option<(TccProbeTarget, TccProbeField)> {
+    let (kind, s) = name.split_once(':')?;
+    let (s, field) = if let Some(x) = s.strip_suffix(":loss") {
+        (x, TccProbeField::Loss)
+    } else if let Some(x) = s.strip_suffix(":ok") {
+        (x, TccProbeField::Ok)
+    } else {
+        (s, TccProbeField::Latency)
+    };
+    let target = match kind {
+        "ping" => {
+            let host = unbracket(s);
+            if host.is_empty() || (host.contains(':') && host.parse::<IpAddr>().is_err()) {
+                return None;
+            }
+            TccProbeTarget::Ping(host.to_string())
+        }
+        "tcp" => {
+            let (host, port) = s.rsplit_once(':')?;
+            let host = unbracket(host);
+            if host.is_empty() || (host.contains(':') && host.parse::<IpAddr>().is_err()) {
+                return None;
+            }
+            TccProbeTarget::Tcp(host.to_string(), port.parse().ok().filter(|x| *x != 0)?)
+        }
+        _ => return None,
+    };
+    Some((target, field))
+}
+
+// 直近の結果  None => timeout, unreachable
+#[derive(Debug, Default, Clone)]
+pub struct TccProbeHistory {
+    results: VecDeque<Option<f64>>,
+}
+
+impl TccProbeHistory {
+    pub fn push(&mut self, result: Option<f64>) {
+        if self.results.len() >= PROBE_HISTORY_SIZE {
+            self.results.pop_front();
+        }
+        self.results.push_back(result);
+    }
+
+    // まだ調べていない => NaN
+    pub fn get_value(&self, field: TccProbeField) -> f64 {
+        let Some(last) = self.results.back() else {
+            return f64::NAN;
+        };
+        match field {
+            TccProbeField::Latency => last.unwrap_or(f64::NAN),
+            TccProbeField::Loss => self.results.iter().filter(|x| x.is_none()).count() as f64 / self.results.len() as f64 * 100.0,
+            TccProbeField::Ok => if last.is_some() { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+pub type TccProbeResults = Arc<Mutex<HashMap<TccProbeTarget, TccProbeHistory>>>;
+
+// names : 使われている placeholder name ("ping:...", "tcp:...")
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn get_probe_values(names: &[String], results: &HashMap<TccProbeTarget, TccProbeHistory>) -> Vec<(String, f64)> {
+    let mut values: Vec<(String, f64)> = Vec::new();
+    for name in names {
+        let Some((target, field)) = parse_probe_name(name) else {
+            continue;
+        };
+        let value = results.get(&target).map(|x| x.get_value(field)).unwrap_or(f64::NAN);
+        values.push((name.to_string(), value));
+    }
+    values
+}
+
+// 名前解決したアドレスに順に接続して、最初につながるまでの時間 (ms)
+// timeout はアドレスごとではなく全体  (名前解決の時間は入らない)
+pub fn probe_tcp(host: &str, port: u16, timeout: Duration) -> Option<f64> {
+    let addresses = (host, port).to_socket_addrs().ok()?;
+    let start = Instant::now();
+    let deadline = start + timeout;
+    for address in addresses {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        if TcpStream::connect_timeout(&address, remaining).is_ok() {
+            return Some(start.elapsed().as_secs_f64() * 1000.0);
+        }
+    }
+    None
+}
+
+// 宛先ごとのスレッドで interval ごとに probe を呼んで results に入れる
+// 応答を待つ間も時計や他の性能値は止まらない  返した Sender を drop すると止まる
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn spawn_prober(target: TccProbeTarget, setting: TccProbeSetting, results: TccProbeResults, probe: fn(&TccProbeTarget, Duration) -> Option<f64>) -> Sender<i32> {
+    let (sender, receiver) = channel::<i32>();
+    spawn(move || {
+        let timeout = Duration::from_millis(setting.timeout.max(1));
+        loop {
+            let result = probe(&target, timeout);
+            results.lock().unwrap().entry(target.clone()).or_default().push(result);
+            match receiver.recv_timeout(Duration::from_secs(setting.interval.max(1))) {
+                Err(RecvTimeoutError::Timeout) => (),
+                _ => break,
+            }
+        }
+    });
+    sender
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn parse_probe_names() {
+        assert_eq!(parse_probe_name("ping:gateway"), Some((TccProbeTarget::Ping("gateway".to_string()), TccProbeField::Latency)));
+        assert_eq!(parse_probe_name("ping:8.8.8.8:loss"), Some((TccProbeTarget::Ping("8.8.8.8".to_string()), TccProbeField::Loss)));
+        assert_eq!(parse_probe_name("ping:[::1]:ok"), Some((TccProbeTarget::Ping("::1".to_string()), TccProbeField::Ok)));
+        assert_eq!(parse_probe_name("tcp:build-server:443"), Some((TccProbeTarget::Tcp("build-server".to_string(), 443), TccProbeField::Latency)));
+        assert_eq!(parse_probe_name("tcp:[::1]:22:loss"), Some((TccProbeTarget::Tcp("::1".to_string(), 22), TccProbeField::Loss)));
+        assert_eq!(parse_probe_name("tcp:build-server"), None);
+        assert_eq!(parse_probe_name("tcp:build-server:0"), None);
+        assert_eq!(parse_probe_name("tcp::443"), None);
+        assert_eq!(parse_probe_name("ping:"), None);
+        assert_eq!(parse_probe_name("ping:fe80::zz"), None);
+        assert_eq!(parse_probe_name("udp:host:53"), None);
+    }
+
+    #[test]
+    fn history_values() {
+        let mut history = TccProbeHistory::default();
+        assert!(history.get_value(TccProbeField::Latency).is_nan());
+        history.push(Some(12.5));
+        history.push(None);
+        assert!(history.get_value(TccProbeField::Latency).is_nan());
+        assert_eq!(history.get_value(TccProbeField::Ok), 0.0);
+        assert_eq!(history.get_value(TccProbeField::Loss), 50.0);
+        for _ in 0..PROBE_HISTORY_SIZE {
+            history.push(Some(3.0));
+        }
+        assert_eq!(history.get_value(TccProbeField::Latency), 3.0);
+        assert_eq!(history.get_value(TccProbeField::Ok), 1.0);
+        assert_eq!(history.get_value(TccProbeField::Loss), 0.0);
+    }
+
+    #[test]
+    fn probe_tcp_connects_to_a_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let latency = probe_tcp("127.0.0.1", port, Duration::from_secs(1)).unwrap();
+        assert!((0.0..1000.0).contains(&latency), "{latency}");
+    }
+
+    #[test]
+    fn probe_tcp_fails_on_a_closed_port() {
+        // 空いているポートを取って閉じる
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let start = Instant::now();
+        assert_eq!(probe_tcp("127.0.0.1", port, Duration::from_millis(500)), None);
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(probe_tcp("127.0.0.1", port, Duration::ZERO), None);
+    }
+}
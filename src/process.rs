@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use regex::{Regex, RegexBuilder};
+use crate::filter;
+
+// プロセスごとの値
+//   {proc:cargo.exe:cpu}         => CPU使用率 (全論理プロセッサで 100%)
+//   {proc:code.exe:mem}          => ワーキングセット (bytes)
+//   {proc:count:rustc.exe}       => プロセス数
+//   {proc:name~'^rust':cpu}      => イメージ名の正規表現 (大文字小文字は区別しない)
+// 同じ名前のプロセスは合計する。".exe" は省略できる
+#[derive(Debug, Clone)]
+pub enum TccProcSelector {
+    Name(String),
+    NameRegex(Regex),
+}
+
+impl PartialEq for TccProcSelector {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (TccProcSelector::Name(x), TccProcSelector::Name(y)) => x == y,
+            (TccProcSelector::NameRegex(x), TccProcSelector::NameRegex(y)) => x.as_str() == y.as_str(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TccProcField {
+    Cpu,
+    Mem,
+    Count,
+}
+
+impl TccProcSelector {
+    pub fn parse(s: &str) -> Option<TccProcSelector> {
+        if s.is_empty() {
+            return None;
+        }
+        if let Some(x) = s.strip_prefix("name~") {
+            let re = RegexBuilder::new(filter::unquote(x)).case_insensitive(true).build().ok()?;
+            return Some(TccProcSelector::NameRegex(re));
+        }
+        Some(TccProcSelector::Name(filter::unquote(s).to_string()))
+    }
+
+    // image name  "cargo.exe"
+    pub fn is_match(&self, image_name: &str) -> bool {
+        match self {
+            TccProcSelector::Name(name) => {
+                image_name.eq_ignore_ascii_case(name)
+                    || image_name.to_ascii_lowercase().strip_suffix(".exe").is_some_and(|x| x.eq_ignore_ascii_case(name))
+            }
+            TccProcSelector::NameRegex(re) => re.is_match(image_name),
+        }
+    }
+}
+
+// "proc:cargo.exe:cpu" => (Name("cargo.exe"), Cpu), "proc:count:rustc.exe" => (Name("rustc.exe"), Count)
+pub fn parse_proc_name(name: &str) -> Option<(TccProcSelector, TccProcField)> {
+    let s = name.strip_prefix("proc:")?;
+    if let Some(x) = s.strip_prefix("count:") {
+        return Some((TccProcSelector::parse(x)?, TccProcField::Count));
+    }
+    let (selector, field) = s.rsplit_once(':')?;
+    let field = match field {
+        "cpu" => TccProcField::Cpu,
+        "mem" => TccProcField::Mem,
+        _ => return None,
+    };
+    Some((TccProcSelector::parse(selector)?, field))
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TccProcess {
+    pub pid: u32,
+    pub image_name: String,
+    // FILETIME (100ns)
+    pub created: u64,
+    // kernel + user (100ns)
+    pub cpu_time: u64,
+    // working set (bytes)
+    pub memory: u64,
+}
+
+// 前回からの CPU 時間
+#[derive(Debug, Default, Clone)]
+pub struct TccProcCounter {
+    // (pid, created) => cpu_time
+    last: HashMap<(u32, u64), u64>,
+    // FILETIME
+    last_time: Option<u64>,
+}
+
+impl TccProcCounter {
+    // (pid => 前回から使った CPU 時間, 前回からの時間)  100ns
+    // 前回の後に起動したプロセスは起動からの全部を数える
+    pub fn update(&mut self, processes: &[TccProcess], now: u64) -> (HashMap<u32, u64>, u64) {
+        let mut used: HashMap<u32, u64> = HashMap::new();
+        let mut current: HashMap<(u32, u64), u64> = HashMap::new();
+        for process in processes {
+            let key = (process.pid, process.created);
+            let last = match (self.last.get(&key), self.last_time) {
+                (Some(x), _) => Some(*x),
+                (None, Some(last_time)) if process.created >= last_time => Some(0),
+                _ => None,
+            };
+            if let Some(x) = last {
+                used.insert(process.pid, process.cpu_time.saturating_sub(x));
+            }
+            current.insert(key, process.cpu_time);
+        }
+        let elapsed = self.last_time.map(|x| now.saturating_sub(x)).unwrap_or(0);
+        self.last = current;
+        self.last_time = Some(now);
+        (used, elapsed)
+    }
+}
+
+// names : 使われている placeholder name ("proc:...")
+// image_names : スナップショットのイメージ名  開けないプロセス (権限がない) も数に入れる
+// processes : 開けたプロセス  cpu, mem はこれで計算する
+pub fn get_proc_values(names: &[String], image_names: &[String], processes: &[TccProcess], used: &HashMap<u32, u64>, elapsed: u64, cpu_count: usize) -> Vec<(String, f64)> {
+    let mut values: Vec<(String, f64)> = Vec::new();
+    for name in names {
+        let Some((selector, field)) = parse_proc_name(name) else {
+            continue;
+        };
+        let matched: Vec<&TccProcess> = processes.iter().filter(|x| selector.is_match(&x.image_name)).collect();
+        let value = match field {
+            TccProcField::Count => image_names.iter().filter(|x| selector.is_match(x)).count() as f64,
+            TccProcField::Mem => matched.iter().map(|x| x.memory as f64).sum(),
+            TccProcField::Cpu => {
+                if elapsed == 0 || cpu_count == 0 {
+                    0.0
+                } else {
+                    let cpu_time: u64 = matched.iter().filter_map(|x| used.get(&x.pid)).sum();
+                    (cpu_time as f64 / (elapsed as f64 * cpu_count as f64) * 100.0).min(100.0)
+                }
+            }
+        };
+        values.push((name.to_string(), value));
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selector(s: &str) -> TccProcSelector {
+        TccProcSelector::parse(s).unwrap()
+    }
+
+    fn process(pid: u32, image_name: &str, created: u64, cpu_time: u64, memory: u64) -> TccProcess {
+        TccProcess { pid, image_name: image_name.to_string(), created, cpu_time, memory }
+    }
+
+    #[test]
+    fn selector_matches_image_names() {
+        let x = selector("cargo");
+        assert!(x.is_match("cargo.exe"));
+        assert!(x.is_match("CARGO.EXE"));
+        assert!(x.is_match("cargo"));
+        assert!(!x.is_match("cargo-clippy.exe"));
+        assert!(!x.is_match(".exe"));
+        assert!(selector("Cargo.exe").is_match("cargo.exe"));
+        // マルチバイトの名前
+        assert!(!x.is_match("テスト"));
+        assert!(!x.is_match("テ.exe"));
+        assert!(selector("テスト").is_match("テスト.exe"));
+        assert!(selector("テスト").is_match("テスト.EXE"));
+        assert!(!selector("テスト").is_match("テスト2.exe"));
+        let x = selector("name~'^rust'");
+        assert!(x.is_match("rustc.exe"));
+        assert!(x.is_match("Rust-Analyzer.exe"));
+        assert!(!x.is_match("cargo.exe"));
+    }
+
+    #[test]
+    fn parse_proc_names() {
+        assert_eq!(parse_proc_name("proc:cargo.exe:cpu"), Some((selector("cargo.exe"), TccProcField::Cpu)));
+        assert_eq!(parse_proc_name("proc:code:mem"), Some((selector("code"), TccProcField::Mem)));
+        assert_eq!(parse_proc_name("proc:count:rustc.exe"), Some((selector("rustc.exe"), TccProcField::Count)));
+        assert_eq!(parse_proc_name("proc:name~'^rust':cpu"), Some((selector("name~^rust"), TccProcField::Cpu)));
+        assert_eq!(parse_proc_name("proc:name~'[':cpu"), None);
+        assert_eq!(parse_proc_name("proc:cargo.exe:disk"), None);
+        assert_eq!(parse_proc_name("proc::cpu"), None);
+        assert_eq!(parse_proc_name("cpu"), None);
+    }
+
+    #[test]
+    fn counter_counts_cpu_time_since_last_update() {
+        let mut counter = TccProcCounter::default();
+        let (used, elapsed) = counter.update(&[process(10, "a.exe", 100, 500, 0)], 1000);
+        assert!(used.is_empty());
+        assert_eq!(elapsed, 0);
+        // 前回の後に起動したプロセス (pid 20) は起動からの全部、pid を使い回したプロセス (pid 10) も同じ
+        let (used, elapsed) = counter.update(&[process(10, "b.exe", 1500, 300, 0), process(20, "c.exe", 1200, 200, 0)], 2000);
+        assert_eq!(used, HashMap::from([(10, 300), (20, 200)]));
+        assert_eq!(elapsed, 1000);
+        let (used, _) = counter.update(&[process(20, "c.exe", 1200, 700, 0)], 3000);
+        assert_eq!(used, HashMap::from([(20, 500)]));
+    }
+
+    #[test]
+    fn proc_values_count_processes_that_cannot_be_opened() {
+        let names: Vec<String> = ["proc:count:svchost", "proc:svchost:cpu", "proc:svchost:mem", "proc:count:missing"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let image_names: Vec<String> = ["svchost.exe", "svchost.exe", "svchost.exe", "code.exe"].iter().map(|x| x.to_string()).collect();
+        // 開けたのは1つだけ
+        let processes = vec![process(100, "svchost.exe", 0, 0, 4096)];
+        let used = HashMap::from([(100, 1000)]);
+        let values = get_proc_values(&names, &image_names, &processes, &used, 1000, 4);
+        assert_eq!(values, vec![
+            ("proc:count:svchost".to_string(), 3.0),
+            ("proc:svchost:cpu".to_string(), 25.0),
+            ("proc:svchost:mem".to_string(), 4096.0),
+            ("proc:count:missing".to_string(), 0.0),
+        ]);
+    }
+}
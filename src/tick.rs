@@ -0,0 +1,113 @@
+use chrono::*;
+
+// 時計の更新タイミング
+// 毎回「次の秒の境目」までの時間を計算するので、処理時間やタイマーの誤差がたまらない
+
+// これ以上遅れたら late
+const LATE_THRESHOLD_MILLIS: i64 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TccTickStatus {
+    OnTime,
+    // milliseconds
+    Late(i64),
+    // seconds not displayed
+    Skipped(i64),
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TccTickScheduler {
+    next_tick: Option<DateTime<Utc>>,
+}
+
+// now の次の秒の境目
+pub fn get_next_second(now: DateTime<Utc>) -> DateTime<Utc> {
+    let second = Utc.timestamp_opt(now.timestamp(), 0).unwrap();
+    second + Duration::seconds(1)
+}
+
+impl TccTickScheduler {
+    // 次の tick までの待ち時間  (None => 今が tick)
+    pub fn get_wait(&mut self, now: DateTime<Utc>) -> Option<std::time::Duration> {
+        // 時計が戻った場合も次の秒の境目より先は待たない
+        let next_second = get_next_second(now);
+        let next_tick = *self.next_tick.insert(self.next_tick.map_or(next_second, |x| x.min(next_second)));
+        if now >= next_tick {
+            None
+        } else {
+            (next_tick - now).to_std().ok()
+        }
+    }
+
+    // tick の処理を始めるときに呼ぶ  遅れを調べて次の tick を決める
+    pub fn start_tick(&mut self, now: DateTime<Utc>) -> TccTickStatus {
+        let status = match self.next_tick {
+            None => TccTickStatus::OnTime,
+            Some(next_tick) => {
+                let late = (now - next_tick).num_milliseconds();
+                if late >= 1000 {
+                    TccTickStatus::Skipped(late / 1000)
+                } else if late > LATE_THRESHOLD_MILLIS {
+                    TccTickStatus::Late(late)
+                } else {
+                    TccTickStatus::OnTime
+                }
+            }
+        };
+        self.next_tick = Some(get_next_second(now));
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 9, 12, 0, 0).unwrap() + Duration::milliseconds(millis)
+    }
+
+    fn millis(x: u64) -> Option<std::time::Duration> {
+        Some(std::time::Duration::from_millis(x))
+    }
+
+    #[test]
+    fn next_second_boundary() {
+        assert_eq!(get_next_second(at(0)), at(1000));
+        assert_eq!(get_next_second(at(1)), at(1000));
+        assert_eq!(get_next_second(at(999)), at(1000));
+        assert_eq!(get_next_second(at(1000)), at(2000));
+    }
+
+    #[test]
+    fn wait_until_the_next_tick() {
+        let mut scheduler = TccTickScheduler::default();
+        assert_eq!(scheduler.get_wait(at(250)), millis(750));
+        assert_eq!(scheduler.get_wait(at(900)), millis(100));
+        // 早く起きても同じ tick を待つ
+        assert_eq!(scheduler.get_wait(at(999)), millis(1));
+        assert_eq!(scheduler.get_wait(at(1000)), None);
+        assert_eq!(scheduler.start_tick(at(1010)), TccTickStatus::OnTime);
+        // 処理時間の分だけ短く待つ
+        assert_eq!(scheduler.get_wait(at(1040)), millis(960));
+    }
+
+    #[test]
+    fn late_and_skipped_ticks() {
+        let mut scheduler = TccTickScheduler::default();
+        assert_eq!(scheduler.start_tick(at(100)), TccTickStatus::OnTime);
+        assert_eq!(scheduler.start_tick(at(1200)), TccTickStatus::OnTime);
+        assert_eq!(scheduler.start_tick(at(2300)), TccTickStatus::Late(300));
+        assert_eq!(scheduler.start_tick(at(5500)), TccTickStatus::Skipped(2));
+        assert_eq!(scheduler.get_wait(at(5600)), millis(400));
+    }
+
+    #[test]
+    fn clock_set_back() {
+        let mut scheduler = TccTickScheduler::default();
+        scheduler.start_tick(at(3_600_000));
+        // 1時間戻っても次の秒の境目まで
+        assert_eq!(scheduler.get_wait(at(300)), millis(700));
+        assert_eq!(scheduler.start_tick(at(1000)), TccTickStatus::OnTime);
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::*;
+
+// TAI, GPS 時刻
+// chrono (Unix時間) はうるう秒を数えないので、うるう秒の表で TAI - UTC を求める
+
+// (UTCの日付, その日からの TAI - UTC 秒)  IERS Bulletin C
+// 新しいうるう秒が発表されたらここに追加する
+const LEAP_SECONDS: [(i32, u32, i64); 28] = [
+    (1972, 1, 10), (1972, 7, 11), (1973, 1, 12), (1974, 1, 13),
+    (1975, 1, 14), (1976, 1, 15), (1977, 1, 16), (1978, 1, 17),
+    (1979, 1, 18), (1980, 1, 19), (1981, 7, 20), (1982, 7, 21),
+    (1983, 7, 22), (1985, 7, 23), (1988, 1, 24), (1990, 1, 25),
+    (1991, 1, 26), (1992, 7, 27), (1993, 7, 28), (1994, 7, 29),
+    (1996, 1, 30), (1997, 7, 31), (1999, 1, 32), (2006, 1, 33),
+    (2009, 1, 34), (2012, 7, 35), (2015, 7, 36), (2017, 1, 37),
+];
+
+// GPS 時刻の起点 1980-01-06 00:00:00 UTC (TAI - UTC = 19)
+const GPS_EPOCH: i64 = 315964800;
+const GPS_TAI_OFFSET: i64 = 19;
+
+// TAI - UTC (seconds)  1972年より前は 10 とする
+pub fn tai_minus_utc(dt: DateTime<Utc>) -> i64 {
+    let date = dt.date_naive();
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|(year, month, _)| NaiveDate::from_ymd_opt(*year, *month, 1).is_some_and(|x| x <= date))
+        .map_or(LEAP_SECONDS[0].2, |x| x.2)
+}
+
+// TAI の日時 (タイムゾーンなし)
+pub fn to_tai(dt: DateTime<Utc>) -> NaiveDateTime {
+    dt.naive_utc() + Duration::seconds(tai_minus_utc(dt))
+}
+
+// GPS の日時 (タイムゾーンなし)  TAI - 19秒
+pub fn to_gps(dt: DateTime<Utc>) -> NaiveDateTime {
+    to_tai(dt) - Duration::seconds(GPS_TAI_OFFSET)
+}
+
+// GPS 起点からの秒数 (うるう秒を含む)
+pub fn gps_seconds(dt: DateTime<Utc>) -> i64 {
+    dt.timestamp() - GPS_EPOCH + tai_minus_utc(dt) - GPS_TAI_OFFSET
+}
+
+// (GPS週, 週の中の秒)
+pub fn gps_week(dt: DateTime<Utc>) -> (i64, i64) {
+    let seconds = gps_seconds(dt);
+    (seconds.div_euclid(604800), seconds.rem_euclid(604800))
+}
+
+// 修正ユリウス日
+pub fn modified_julian_day(jd: f64) -> f64 {
+    jd - 2400000.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timezone::TccTimezone;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn tai_minus_utc_at_leap_seconds() {
+        assert_eq!(tai_minus_utc(utc(2016, 12, 31, 23, 59, 59)), 36);
+        // chrono の 23:59:60 (ナノ秒が 10億以上) もまだ 36
+        let leap = NaiveDate::from_ymd_opt(2016, 12, 31).unwrap().and_hms_milli_opt(23, 59, 59, 1500).unwrap();
+        assert_eq!(tai_minus_utc(Utc.from_utc_datetime(&leap)), 36);
+        assert_eq!(tai_minus_utc(utc(2017, 1, 1, 0, 0, 0)), 37);
+        assert_eq!(tai_minus_utc(utc(2015, 6, 30, 23, 59, 59)), 35);
+        assert_eq!(tai_minus_utc(utc(2015, 7, 1, 0, 0, 0)), 36);
+        assert_eq!(tai_minus_utc(utc(1970, 1, 1, 0, 0, 0)), 10);
+        assert_eq!(tai_minus_utc(utc(2024, 1, 1, 0, 0, 0)), 37);
+    }
+
+    #[test]
+    fn tai_and_gps_datetime() {
+        let dt = utc(2024, 3, 9, 12, 0, 0);
+        assert_eq!(to_tai(dt), NaiveDate::from_ymd_opt(2024, 3, 9).unwrap().and_hms_opt(12, 0, 37).unwrap());
+        assert_eq!(to_gps(dt), NaiveDate::from_ymd_opt(2024, 3, 9).unwrap().and_hms_opt(12, 0, 18).unwrap());
+    }
+
+    #[test]
+    fn gps_week_at_known_epochs() {
+        // GPS 起点
+        assert_eq!(gps_week(utc(1980, 1, 6, 0, 0, 0)), (0, 0));
+        // 週番号 1024 (1999/08/22 GPS 時刻 0時)
+        assert_eq!(gps_week(utc(1999, 8, 21, 23, 59, 47)), (1024, 0));
+        // 2017/01/01 0時 UTC は GPS 週 1930 の 18 秒目
+        assert_eq!(gps_week(utc(2017, 1, 1, 0, 0, 0)), (1930, 18));
+        // 週番号 2048 (2019/04/07 GPS 時刻 0時)
+        assert_eq!(gps_week(utc(2019, 4, 6, 23, 59, 42)), (2048, 0));
+        assert_eq!(gps_week(utc(2019, 4, 6, 23, 59, 41)), (2047, 604799));
+    }
+
+    #[test]
+    fn modified_julian_day_epoch() {
+        assert_eq!(modified_julian_day(2400000.5), 0.0);
+        assert_eq!(modified_julian_day(2451545.0), 51544.5);
+        assert_eq!(modified_julian_day(crate::astro::julian_day(utc(1858, 11, 17, 0, 0, 0))), 0.0);
+    }
+
+    // {unix} {unix_ms} {ordinal}  ラベルのタイムゾーンの日時 (ordinal はその日付)
+    #[test]
+    fn unix_time_and_ordinal() {
+        let jst = TccTimezone::Fixed(FixedOffset::east_opt(9 * 3600).unwrap());
+        let now = (utc(2024, 12, 31, 12, 0, 0) + Duration::milliseconds(345)).with_timezone(&jst);
+        assert_eq!(now.timestamp(), 1735646400);
+        assert_eq!(now.timestamp_millis(), 1735646400345);
+        assert_eq!(utc(1970, 1, 1, 0, 0, 0).timestamp(), 0);
+        assert_eq!(utc(2000, 1, 1, 12, 0, 0).timestamp(), 946728000);
+        // うるう年の 366 日目、JST では次の年の 1 日目
+        assert_eq!(now.format("%Y-%j").to_string(), "2024-366");
+        assert_eq!(utc(2024, 12, 31, 15, 0, 0).with_timezone(&jst).format("%Y-%j").to_string(), "2025-001");
+        assert_eq!(utc(2023, 12, 31, 14, 59, 59).with_timezone(&jst).format("%Y-%j").to_string(), "2023-365");
+        assert_eq!(utc(2024, 2, 29, 0, 0, 0).format("%Y-%j").to_string(), "2024-060");
+    }
+}
@@ -0,0 +1,362 @@
+use std::{
+    fmt,
+    collections::HashMap,
+    sync::Mutex,
+    time::Instant,
+};
+use once_cell::sync::Lazy;
+use chrono::*;
+use chrono_tz::{Tz, OffsetComponents};
+use anyhow::*;
+
+// label > timezone
+//   "Asia/Tokyo"          => chrono-tz (IANA)
+//   "local", ""           => Windows のタイムゾーン (実行中の変更にも追従)
+//   "+05:45", "UTC-3"     => 固定オフセット
+//   "JST"                 => 別名 (config.txt > timezone_aliases, BUILTIN_ALIASES)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TccTimezone {
+    #[default]
+    Local,
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+// Local : IANA名に変換できない Windows のタイムゾーン  時刻ごとに chrono::Local で求める
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TccOffset {
+    Named(<Tz as TimeZone>::Offset),
+    Fixed(FixedOffset),
+    Local(FixedOffset),
+}
+
+// chrono-tz にない略称  config.txt > timezone_aliases が優先
+// 夏時間のある地域は標準時・夏時間のどちらの略称も夏時間に追従するタイムゾーンにする
+// (chrono-tz の EST, MST は固定オフセットなので上書きする)
+const BUILTIN_ALIASES: [(&str, &str); 19] = [
+    ("JST", "Asia/Tokyo"),
+    ("KST", "Asia/Seoul"),
+    ("IST", "Asia/Kolkata"),
+    ("SGT", "Asia/Singapore"),
+    ("HKT", "Asia/Hong_Kong"),
+    ("AEST", "Australia/Sydney"),
+    ("AEDT", "Australia/Sydney"),
+    ("NZST", "Pacific/Auckland"),
+    ("NZDT", "Pacific/Auckland"),
+    ("BST", "Europe/London"),
+    ("CEST", "Europe/Berlin"),
+    ("EST", "America/New_York"),
+    ("EDT", "America/New_York"),
+    ("CST", "America/Chicago"),
+    ("CDT", "America/Chicago"),
+    ("MST", "America/Denver"),
+    ("MDT", "America/Denver"),
+    ("PST", "America/Los_Angeles"),
+    ("PDT", "America/Los_Angeles"),
+];
+
+// Windows のタイムゾーンは10秒ごとに確認する
+const LOCAL_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Default)]
+struct TccLocalTz {
+    tz: Option<Tz>,
+    checked: Option<Instant>,
+}
+
+static GLOBAL_LOCAL_TZ: Lazy<Mutex<TccLocalTz>> = Lazy::new(|| Mutex::new(TccLocalTz::default()));
+
+// Windows のタイムゾーン (IANA名に変換できない場合は None)
+pub fn get_local_tz() -> Option<Tz> {
+    let mut local_tz = GLOBAL_LOCAL_TZ.lock().unwrap();
+    if local_tz.checked.is_none_or(|x| x.elapsed() >= LOCAL_REFRESH_INTERVAL) {
+        local_tz.tz = iana_time_zone::get_timezone().ok().and_then(|x| x.parse::<Tz>().ok());
+        local_tz.checked = Some(Instant::now());
+    }
+    local_tz.tz
+}
+
+// "+09:00", "+0900", "+9", "UTC+05:45", "GMT-3"
+pub fn parse_fixed_offset(s: &str) -> Option<FixedOffset> {
+    let s = s.trim();
+    let s = s.strip_prefix("UTC").or(s.strip_prefix("GMT")).unwrap_or(s);
+    let (sign, s) = match s.chars().next()? {
+        '+' => (1, &s[1..]),
+        '-' => (-1, &s[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match s.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if s.len() == 4 => s.split_at(2),
+        None => (s, "0"),
+    };
+    if hours.is_empty() || hours.len() > 2 || !hours.chars().all(|x| x.is_ascii_digit()) || !minutes.chars().all(|x| x.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+pub fn parse_timezone(s: &str, aliases: &HashMap<String, String>) -> anyhow::Result<TccTimezone> {
+    let s = s.trim();
+    if let Some(x) = aliases.get(s).map(|x| x.as_str()).or(BUILTIN_ALIASES.iter().find(|x| x.0 == s).map(|x| x.1)) {
+        // config.txt の別名から組み込みの別名は使えるが、config.txt の別名の別名は使えない
+        return parse_timezone(x, &HashMap::new()).map_err(|_| anyhow!(format!("incorrect alias : {s} => {x}")));
+    }
+    if s.is_empty() || s.eq_ignore_ascii_case("local") {
+        return Ok(TccTimezone::Local);
+    }
+    if let std::result::Result::Ok(x) = s.parse::<Tz>() {
+        return Ok(TccTimezone::Named(x));
+    }
+    if let Some(x) = parse_fixed_offset(s) {
+        return Ok(TccTimezone::Fixed(x));
+    }
+    Err(anyhow!(format!("unknown timezone : {s}")))
+}
+
+// 時差の変更を探す期間
+const MAX_SEARCH_DAYS: i64 = 400;
+
+// get_next_offset_change の結果  (探し始めた時刻, 時差が変わる時刻)
+// 変わる時刻を過ぎるまで (見つからなければ1日) は探し直さない
+type TccOffsetChange = (DateTime<Utc>, Option<DateTime<Utc>>);
+static GLOBAL_OFFSET_CHANGE: Lazy<Mutex<HashMap<TccTimezone, TccOffsetChange>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl TccTimezone {
+    // Local => その時点の Windows のタイムゾーン  IANA名に変換できない場合は Local のまま
+    fn resolve(&self) -> TccTimezone {
+        match self {
+            TccTimezone::Local => get_local_tz().map_or(TccTimezone::Local, TccTimezone::Named),
+            x => *x,
+        }
+    }
+
+    // "Asia/Tokyo", "+05:45"
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn get_name(&self) -> String {
+        match self.resolve() {
+            TccTimezone::Named(tz) => tz.name().to_string(),
+            TccTimezone::Fixed(x) => x.to_string(),
+            TccTimezone::Local => Local::now().offset().to_string(),
+        }
+    }
+
+    // from より後で最初に時差が変わる時刻  (MAX_SEARCH_DAYS 以内)
+    pub fn get_next_offset_change(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let tz = self.resolve();
+        // IANA名に変換できない Windows のタイムゾーンは中身が変わっても分からないので毎回探す
+        if tz == TccTimezone::Local {
+            return tz.search_next_offset_change(from);
+        }
+        let mut cache = GLOBAL_OFFSET_CHANGE.lock().unwrap();
+        if let Some((start, change)) = cache.get(&tz) {
+            let end = change.unwrap_or(*start + Duration::days(1));
+            if *start <= from && from < end {
+                return *change;
+            }
+        }
+        let change = tz.search_next_offset_change(from);
+        cache.insert(tz, (from, change));
+        change
+    }
+
+    fn search_next_offset_change(&self, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let tz = *self;
+        let get_offset = |x: DateTime<Utc>| tz.offset_from_utc_datetime(&x.naive_utc()).fix();
+        let offset = get_offset(from);
+        let high = (1..=MAX_SEARCH_DAYS)
+            .map(|x| from + Duration::days(x))
+            .find(|x| get_offset(*x) != offset)?;
+        // 1秒単位まで絞り込む
+        let mut low = from.max(high - Duration::days(1)).timestamp();
+        let mut high = high.timestamp();
+        while high - low > 1 {
+            let middle = low + (high - low) / 2;
+            if get_offset(Utc.timestamp_opt(middle, 0).unwrap()) == offset {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        Utc.timestamp_opt(high, 0).single()
+    }
+}
+
+pub fn is_dst(offset: &TccOffset) -> bool {
+    match offset {
+        TccOffset::Named(x) => x.dst_offset() != Duration::zero(),
+        TccOffset::Fixed(_) | TccOffset::Local(_) => false,
+    }
+}
+
+impl TimeZone for TccTimezone {
+    type Offset = TccOffset;
+
+    fn from_offset(offset: &TccOffset) -> TccTimezone {
+        match offset {
+            TccOffset::Named(x) => TccTimezone::Named(Tz::from_offset(x)),
+            TccOffset::Fixed(x) => TccTimezone::Fixed(*x),
+            TccOffset::Local(_) => TccTimezone::Local,
+        }
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<TccOffset> {
+        match self.resolve() {
+            TccTimezone::Named(tz) => tz.offset_from_local_date(local).map(TccOffset::Named),
+            TccTimezone::Fixed(x) => LocalResult::Single(TccOffset::Fixed(x)),
+            TccTimezone::Local => Local.offset_from_local_date(local).map(TccOffset::Local),
+        }
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<TccOffset> {
+        match self.resolve() {
+            TccTimezone::Named(tz) => tz.offset_from_local_datetime(local).map(TccOffset::Named),
+            TccTimezone::Fixed(x) => LocalResult::Single(TccOffset::Fixed(x)),
+            TccTimezone::Local => Local.offset_from_local_datetime(local).map(TccOffset::Local),
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> TccOffset {
+        match self.resolve() {
+            TccTimezone::Named(tz) => TccOffset::Named(tz.offset_from_utc_date(utc)),
+            TccTimezone::Fixed(x) => TccOffset::Fixed(x),
+            TccTimezone::Local => TccOffset::Local(Local.offset_from_utc_date(utc)),
+        }
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> TccOffset {
+        match self.resolve() {
+            TccTimezone::Named(tz) => TccOffset::Named(tz.offset_from_utc_datetime(utc)),
+            TccTimezone::Fixed(x) => TccOffset::Fixed(x),
+            TccTimezone::Local => TccOffset::Local(Local.offset_from_utc_datetime(utc)),
+        }
+    }
+}
+
+impl Offset for TccOffset {
+    fn fix(&self) -> FixedOffset {
+        match self {
+            TccOffset::Named(x) => x.fix(),
+            TccOffset::Fixed(x) | TccOffset::Local(x) => *x,
+        }
+    }
+}
+
+// %Z  => "JST", "CEST", "+05:45"
+impl fmt::Display for TccOffset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TccOffset::Named(x) => write!(f, "{x}"),
+            TccOffset::Fixed(x) | TccOffset::Local(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn parse(s: &str) -> anyhow::Result<TccTimezone> {
+        parse_timezone(s, &HashMap::new())
+    }
+
+    #[test]
+    fn parse_timezone_names_and_offsets() {
+        assert_eq!(parse("Asia/Tokyo").unwrap(), TccTimezone::Named(Tz::Asia__Tokyo));
+        assert_eq!(parse(" Europe/Berlin ").unwrap(), TccTimezone::Named(Tz::Europe__Berlin));
+        assert_eq!(parse("").unwrap(), TccTimezone::Local);
+        assert_eq!(parse("LOCAL").unwrap(), TccTimezone::Local);
+        assert_eq!(parse("+05:45").unwrap(), TccTimezone::Fixed(FixedOffset::east_opt(20700).unwrap()));
+        assert_eq!(parse("UTC-3").unwrap(), TccTimezone::Fixed(FixedOffset::west_opt(10800).unwrap()));
+        assert_eq!(parse("GMT+0930").unwrap(), TccTimezone::Fixed(FixedOffset::east_opt(34200).unwrap()));
+        assert!(parse("+15").is_err());
+        assert!(parse("+09:60").is_err());
+        assert!(parse("Mars/Olympus").is_err());
+    }
+
+    #[test]
+    fn parse_timezone_aliases() {
+        // 標準時・夏時間の略称は同じタイムゾーン
+        for (standard, daylight, name) in [
+            ("EST", "EDT", Tz::America__New_York),
+            ("CST", "CDT", Tz::America__Chicago),
+            ("MST", "MDT", Tz::America__Denver),
+            ("PST", "PDT", Tz::America__Los_Angeles),
+            ("AEST", "AEDT", Tz::Australia__Sydney),
+            ("NZST", "NZDT", Tz::Pacific__Auckland),
+        ] {
+            assert_eq!(parse(standard).unwrap(), TccTimezone::Named(name), "{standard}");
+            assert_eq!(parse(daylight).unwrap(), TccTimezone::Named(name), "{daylight}");
+        }
+        assert_eq!(parse("JST").unwrap(), TccTimezone::Named(Tz::Asia__Tokyo));
+
+        // config.txt の別名が優先  組み込みの別名は使えるが別名の別名は使えない
+        let aliases = HashMap::from([
+            ("CST".to_string(), "Asia/Shanghai".to_string()),
+            ("HQ".to_string(), "JST".to_string()),
+            ("LAB".to_string(), "HQ".to_string()),
+        ]);
+        assert_eq!(parse_timezone("CST", &aliases).unwrap(), TccTimezone::Named(Tz::Asia__Shanghai));
+        assert_eq!(parse_timezone("HQ", &aliases).unwrap(), TccTimezone::Named(Tz::Asia__Tokyo));
+        assert!(parse_timezone("LAB", &aliases).is_err());
+    }
+
+    #[test]
+    fn alias_follows_daylight_saving() {
+        let tz = parse("EST").unwrap();
+        assert_eq!(tz.offset_from_utc_datetime(&utc(2024, 1, 15, 12).naive_utc()).fix().local_minus_utc(), -5 * 3600);
+        assert_eq!(tz.offset_from_utc_datetime(&utc(2024, 7, 15, 12).naive_utc()).fix().local_minus_utc(), -4 * 3600);
+        assert!(is_dst(utc(2024, 7, 15, 12).with_timezone(&tz).offset()));
+        assert_eq!(utc(2024, 1, 15, 12).with_timezone(&tz).format("%Z").to_string(), "EST");
+    }
+
+    #[test]
+    fn local_offset_is_resolved_per_instant() {
+        // 冬と夏で Windows のタイムゾーン (chrono::Local) と同じ時差になる
+        for dt in [utc(2024, 1, 15, 12), utc(2024, 7, 15, 12)] {
+            let offset = TccTimezone::Local.offset_from_utc_datetime(&dt.naive_utc()).fix();
+            assert_eq!(offset, Local.offset_from_utc_datetime(&dt.naive_utc()), "{dt}");
+        }
+        let offset = TccTimezone::Local.offset_from_utc_datetime(&utc(2024, 1, 15, 12).naive_utc());
+        assert_eq!(TccTimezone::from_offset(&TccOffset::Local(offset.fix())), TccTimezone::Local);
+    }
+
+    #[test]
+    fn next_offset_change_dst() {
+        let tz = parse("Europe/Berlin").unwrap();
+        let spring = Utc.with_ymd_and_hms(2024, 3, 31, 1, 0, 0).unwrap();
+        let autumn = Utc.with_ymd_and_hms(2024, 10, 27, 1, 0, 0).unwrap();
+        assert_eq!(tz.get_next_offset_change(utc(2024, 3, 1, 0)), Some(spring));
+        // 変わる時刻までは同じ結果、過ぎたら次の変更
+        assert_eq!(tz.get_next_offset_change(utc(2024, 3, 20, 0)), Some(spring));
+        assert_eq!(tz.get_next_offset_change(spring - Duration::seconds(1)), Some(spring));
+        assert_eq!(tz.get_next_offset_change(spring), Some(autumn));
+        // 時計が戻った場合も探し直す
+        assert_eq!(tz.get_next_offset_change(utc(2023, 12, 1, 0)), Some(spring));
+        assert_eq!(tz.search_next_offset_change(utc(2024, 3, 1, 0)), Some(spring));
+    }
+
+    #[test]
+    fn next_offset_change_base_offset() {
+        // ベネズエラ 2016/05/01 02:30 (-04:30 => -04:00)
+        let tz = parse("America/Caracas").unwrap();
+        let change = Utc.with_ymd_and_hms(2016, 5, 1, 7, 0, 0).unwrap();
+        assert_eq!(tz.get_next_offset_change(utc(2016, 1, 1, 0)), Some(change));
+        assert_eq!(tz.get_next_offset_change(change), None);
+        // ボルゴグラード 2020/12/27 02:00 (+03:00 => +04:00)
+        let tz = parse("Europe/Volgograd").unwrap();
+        assert_eq!(tz.get_next_offset_change(utc(2020, 10, 1, 0)), Some(utc(2020, 12, 26, 22)));
+        // 夏時間も時差の変更もない
+        assert_eq!(parse("Asia/Tokyo").unwrap().get_next_offset_change(utc(2024, 1, 1, 0)), None);
+        assert_eq!(parse("+05:45").unwrap().get_next_offset_change(utc(2024, 1, 1, 0)), None);
+    }
+}